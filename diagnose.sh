@@ -11,16 +11,15 @@ echo "2. Checking if jumper is a function or binary..."
 type jumper
 echo ""
 
-echo "3. Checking if shell integration is sourced in .bashrc..."
-grep -n "jumper.sh" ~/.bashrc
+echo "3. Checking if shell integration is loaded in .bashrc..."
+grep -n "jumper init\|jumper.sh" ~/.bashrc
 echo ""
 
-echo "4. Checking if lastdir file exists..."
-if [ -f ~/.cache/jumper/lastdir ]; then
-    echo "✓ File exists: ~/.cache/jumper/lastdir"
-    echo "  Contents: $(cat ~/.cache/jumper/lastdir)"
+echo "4. Checking if the wrapper uses --cwd-file..."
+if type jumper 2>/dev/null | grep -q -- "--cwd-file"; then
+    echo "✓ Wrapper is up to date (quit with Q to cd)"
 else
-    echo "✗ File does NOT exist: ~/.cache/jumper/lastdir"
+    echo "✗ Wrapper is missing or outdated; use: eval \"\$(jumper init bash)\""
 fi
 echo ""
 
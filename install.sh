@@ -113,42 +113,21 @@ else
     echo -e "${GREEN}$INSTALL_DIR is already in your PATH${NC}"
 fi
 
-# Install shell integration scripts
-SHELL_DIR="$HOME/.local/share/jumper/shell"
-mkdir -p "$SHELL_DIR"
-
-# Determine script location
-SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
-if [ -d "$SCRIPT_DIR/shell" ]; then
-    # Installing from source
-    cp "$SCRIPT_DIR/shell/jumper.sh" "$SHELL_DIR/jumper.sh"
-    cp "$SCRIPT_DIR/shell/jumper.fish" "$SHELL_DIR/jumper.fish"
-else
-    # Download shell integration scripts
-    curl -sL "https://raw.githubusercontent.com/$REPO/main/shell/jumper.sh" -o "$SHELL_DIR/jumper.sh"
-    curl -sL "https://raw.githubusercontent.com/$REPO/main/shell/jumper.fish" -o "$SHELL_DIR/jumper.fish"
-fi
-
 echo ""
 echo -e "${YELLOW}━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━${NC}"
 echo -e "${YELLOW}  Shell Integration (Required)${NC}"
 echo -e "${YELLOW}━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━${NC}"
 echo ""
-echo "Shell integration scripts installed to: $SHELL_DIR"
-echo ""
 echo "Add ONE of these lines to your shell config:"
 echo ""
 echo -e "${GREEN}Bash (~/.bashrc):${NC}"
-echo "  source $SHELL_DIR/jumper.sh"
+echo '  eval "$(jumper init bash)"'
 echo ""
 echo -e "${GREEN}Zsh (~/.zshrc):${NC}"
-echo "  source $SHELL_DIR/jumper.sh"
+echo '  eval "$(jumper init zsh)"'
 echo ""
 echo -e "${GREEN}Fish (~/.config/fish/config.fish):${NC}"
-echo "  source $SHELL_DIR/jumper.fish"
+echo "  jumper init fish | source"
 echo ""
 echo "Then reload your shell or run: source ~/.bashrc (or ~/.zshrc)"
-echo ""
-echo -e "${YELLOW}━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━${NC}"
-echo ""
-echo "After setup, run 'jumper' - it will automatically cd on exit!"
+echo "Quit jumper with Q to cd into the directory you were browsing."
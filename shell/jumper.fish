@@ -1,15 +1,19 @@
 # Jumper shell integration for Fish
 # Add this to your ~/.config/fish/config.fish:
-#   source /path/to/jumper.fish
+#   jumper init fish | source
+#
+# Quitting jumper with Q changes the shell to the directory you were in;
+# quitting with q leaves the shell where it was.
 
 function jumper --wraps=jumper
-    command jumper $argv
-    set exit_code $status
-    if test $exit_code -eq 0 -a -f "$HOME/.cache/jumper/lastdir"
-        set target_dir (cat "$HOME/.cache/jumper/lastdir")
-        if test -d "$target_dir"
-            cd "$target_dir"
-        end
+    set -l cwd_file (mktemp -t jumper-cwd.XXXXXX)
+    or return
+    command jumper --cwd-file $cwd_file $argv
+    set -l exit_code $status
+    set -l target_dir (cat -- $cwd_file)
+    rm -f -- $cwd_file
+    if test -n "$target_dir" -a -d "$target_dir" -a "$target_dir" != "$PWD"
+        cd -- $target_dir
     end
     return $exit_code
 end
@@ -1,17 +1,20 @@
 #!/bin/sh
 # Jumper shell integration for Bash and Zsh
 # Add this to your ~/.bashrc or ~/.zshrc:
-#   source /path/to/jumper.sh
+#   eval "$(jumper init bash)"   # or: eval "$(jumper init zsh)"
+#
+# Quitting jumper with Q changes the shell to the directory you were in;
+# quitting with q leaves the shell where it was.
 
 jumper() {
-    command jumper "$@"
-    local exit_code=$?
-    if [ $exit_code -eq 0 ] && [ -f "$HOME/.cache/jumper/lastdir" ]; then
-        local target_dir
-        target_dir="$(cat "$HOME/.cache/jumper/lastdir")"
-        if [ -d "$target_dir" ]; then
-            cd "$target_dir" || return
-        fi
+    local cwd_file exit_code target_dir
+    cwd_file="$(mktemp -t jumper-cwd.XXXXXX)" || return
+    command jumper --cwd-file "$cwd_file" "$@"
+    exit_code=$?
+    target_dir="$(cat -- "$cwd_file")"
+    rm -f -- "$cwd_file"
+    if [ -n "$target_dir" ] && [ -d "$target_dir" ] && [ "$target_dir" != "$PWD" ]; then
+        cd -- "$target_dir" || return
     fi
     return $exit_code
 }
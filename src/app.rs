@@ -1,10 +1,25 @@
+use crate::archive::Archive;
+use crate::bookmarks::Bookmarks;
 use crate::config::{Config, SortMode};
+use crate::file_ops::{Journal, Operation, PendingPaste};
+use crate::finder::Finder;
+use crate::frecency::{Frecency, FrecencyMatch};
+use crate::grep::Grep;
+use crate::jobs::{JobKind, JobQueue};
+use crate::keymap::{KeyChord, Keymap};
+use crate::opener::Opener;
+use crate::preview::Preview;
+use crate::rename::{PatternRename, RenamePlan};
+use crate::trash::TrashItem;
+use crate::watcher::DirWatcher;
 use anyhow::Result;
 use ratatui::widgets::ListState;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::Write;
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::mpsc;
 
@@ -37,6 +52,30 @@ pub enum Mode {
     Rename,
     Help,
     DeleteConfirm,
+    Trash,
+    PurgeConfirm,
+    Jobs,
+    PasteConflict,
+    Finder,
+    Filter,
+    GrepInput,
+    Grep,
+    Jump,
+    OpenWith,
+    RenameConfirm,
+    PatternRename,
+    Compress,
+    BookmarkSet,
+    BookmarkJump,
+    Bookmarks,
+}
+
+/// A program that needs the terminal; `run_app` suspends the TUI while it runs
+#[derive(Debug, Clone)]
+pub enum External {
+    Edit(Vec<PathBuf>),
+    Open(Opener, PathBuf), // a terminal opener
+    BulkRename { file: PathBuf, originals: Vec<PathBuf> }, // names listed in `file`, one per line
 }
 
 #[derive(Debug, Clone)]
@@ -45,131 +84,296 @@ pub struct NavigationHistory {
     pub selected_index: usize,
 }
 
-pub struct App {
+/// Everything that belongs to one location: a tab (or a pane) has its own directory,
+/// listing, cursor, histories and search state
+#[derive(Default)]
+pub struct Tab {
     pub current_dir: PathBuf,
     pub files: Vec<FileEntry>,
     pub list_state: ListState,
+    pub filtered_indices: Vec<usize>,
+    pub nav_history: Vec<NavigationHistory>,
+    pub global_history: Vec<NavigationHistory>, // Global navigation history for Ctrl+O
+    pub search_query: String,
+    pub search_highlights: Vec<usize>,
+    pub search_match_positions: HashMap<usize, Vec<usize>>, // file index -> character positions
+    pub filter_query: String, // hides non-matching entries while set
+    pub filter_match_positions: HashMap<usize, Vec<usize>>, // file index -> character positions
+    pub dir_sizes: HashMap<PathBuf, Option<u64>>, // None means still calculating
+    pub parent_files: Vec<FileEntry>, // parent directory listing for the miller layout
+    pub archive: Option<Arc<Archive>>, // set while browsing inside an archive
+}
+
+impl Tab {
+    pub fn new(current_dir: PathBuf) -> Self {
+        Self {
+            current_dir,
+            ..Self::default()
+        }
+    }
+
+    pub fn load_directory(&mut self, show_hidden: bool, sort_mode: &SortMode, ascending: bool) -> Result<()> {
+        // Paths below an archive file are listed from the archive's index, read once per archive
+        self.archive = match crate::archive::locate(&self.current_dir) {
+            Some(path) => match self.archive.take() {
+                Some(archive) if archive.path == path && !archive.is_stale() => Some(archive),
+                _ => Some(Arc::new(Archive::open(&path)?)),
+            },
+            None => None,
+        };
+        let entries = match &self.archive {
+            Some(archive) => archive.read_dir(&self.current_dir, show_hidden)?,
+            None => read_entries(&self.current_dir, show_hidden)?,
+        };
+        self.files = sort_entries(entries, sort_mode, ascending);
+        self.dir_sizes.clear(); // Clear old directory sizes
+
+        // Mark directories for size calculation; an archive already knows them
+        for file in &self.files {
+            if file.is_dir {
+                let size = self.archive.as_ref().map(|_| file.size);
+                self.dir_sizes.insert(file.path.clone(), size);
+            }
+        }
+
+        // The filter stays active when the listing is reloaded
+        self.update_filtered_indices();
+        Ok(())
+    }
+
+    /// Whether the listed directory (or the archive it is in) no longer exists
+    fn is_gone(&self) -> bool {
+        match &self.archive {
+            Some(archive) => !archive.path.is_file(),
+            None => !self.current_dir.is_dir(),
+        }
+    }
+
+    /// Recomputes the visible entries: all of them, or only filter matches ordered by score
+    pub fn update_filtered_indices(&mut self) {
+        if self.filter_query.is_empty() {
+            self.filtered_indices = (0..self.files.len()).collect();
+            self.filter_match_positions.clear();
+        } else {
+            let (indices, positions) = crate::fuzzy::filter_files(&self.files, &self.filter_query);
+            self.filtered_indices = indices;
+            self.filter_match_positions = positions;
+        }
+    }
+}
+
+pub struct App {
+    pub tab: Tab,       // the active tab
+    pub tabs: Vec<Tab>, // all tabs; the slot at `active_tab` is empty while that tab is active
+    pub active_tab: usize,
+    pub other_pane: Option<Tab>, // the unfocused panel in dual-pane mode
+    pub focus_left: bool,        // whether the focused panel is drawn on the left
     pub clipboard: ClipboardOperation,
     pub mode: Mode,
     pub show_hidden: bool,
     pub sort_mode: SortMode,
     pub sort_ascending: bool,
-    pub search_query: String,
     pub create_input: String,
+    pub compress_input: String,
+    pub compress_sources: Vec<PathBuf>,
     pub rename_input: String,
     pub rename_cursor_pos: usize,
     pub rename_target: Option<PathBuf>,
-    pub filtered_indices: Vec<usize>,
     pub selected_paths: Vec<PathBuf>,
     pub should_quit: bool,
     pub config: Config,
-    pub last_key: String,
-    pub nav_history: Vec<NavigationHistory>,
+    pub normal_keys: Keymap,
+    pub visual_keys: Keymap,
+    pub pending_keys: Vec<KeyChord>, // start of a multi-key sequence
+    pub pending_count: Option<usize>, // count prefix typed before a command, e.g. 5j
     pub flash_copied_paths: Vec<PathBuf>,
     pub delete_targets: Vec<PathBuf>,
-    pub search_highlights: Vec<usize>,
-    pub search_match_positions: HashMap<usize, Vec<usize>>, // file index -> character positions
     pub error_message: Option<String>,
-    pub global_history: Vec<NavigationHistory>, // Global navigation history for Ctrl+O
-    pub dir_sizes: HashMap<PathBuf, Option<u64>>, // None means still calculating
+    pub status_message: Option<String>,
     pub dir_size_rx: mpsc::UnboundedReceiver<(PathBuf, u64)>,
     pub dir_size_tx: mpsc::UnboundedSender<(PathBuf, u64)>,
+    pub trash_items: Vec<TrashItem>,
+    pub trash_state: ListState,
+    pub journal: Journal,
+    pub jobs: JobQueue,
+    pub jobs_state: ListState,
+    pub pending_paste: Option<PendingPaste>,
+    pub show_preview: bool,
+    pub preview: Option<(PathBuf, Preview)>,
+    pub preview_path: Option<PathBuf>, // path whose preview was last requested
+    pub preview_rx: mpsc::UnboundedReceiver<(PathBuf, Preview)>,
+    pub preview_tx: mpsc::UnboundedSender<(PathBuf, Preview)>,
+    pub miller_columns: bool,
+    pub finder: Option<Finder>,
+    pub grep: Option<Grep>,
+    pub grep_input: String,
+    pub bookmarks: Bookmarks,
+    pub bookmarks_state: ListState,
+    pub frecency: Frecency,
+    pub jump_input: String,
+    pub jump_matches: Vec<FrecencyMatch>,
+    pub jump_state: ListState,
+    pub choose_files: bool,         // opening a file picks it instead (--choose-files)
+    pub chosen_files: Vec<PathBuf>,
+    pub cd_on_quit: bool,           // the shell wrapper should cd into current_dir
+    pub external: Option<External>,
+    pub open_with_path: Option<PathBuf>,
+    pub open_with_choices: Vec<Option<Opener>>, // None is the system default
+    pub open_with_state: ListState,
+    pub watcher: Option<DirWatcher>,
+    pub rename_plan: Option<RenamePlan>, // waiting for confirmation
+    pub rename_plan_state: ListState,
+    pub pattern_rename: Option<PatternRename>,
 }
 
 impl App {
-    pub fn new(config: Config) -> Result<Self> {
-        let current_dir = std::env::current_dir()?;
+    pub fn new(config: Config, current_dir: PathBuf) -> Result<Self> {
         let show_hidden = config.behavior.show_hidden;
         let sort_mode = config.behavior.default_sort.clone();
 
         let (dir_size_tx, dir_size_rx) = mpsc::unbounded_channel();
+        let (preview_tx, preview_rx) = mpsc::unbounded_channel();
+        let show_preview = config.behavior.preview;
+        let miller_columns = config.behavior.miller_columns;
+        let (normal_keys, mut keymap_errors) = Keymap::normal(&config.keybindings);
+        let (visual_keys, visual_errors) = Keymap::visual(&config.keybindings);
+        keymap_errors.extend(visual_errors);
 
         let mut app = Self {
-            current_dir: current_dir.clone(),
-            files: Vec::new(),
-            list_state: ListState::default(),
+            tab: Tab::new(current_dir),
+            tabs: vec![Tab::default()],
+            active_tab: 0,
+            other_pane: None,
+            focus_left: true,
             clipboard: ClipboardOperation::None,
             mode: Mode::Normal,
             show_hidden,
             sort_mode,
             sort_ascending: true,
-            search_query: String::new(),
             create_input: String::new(),
+            compress_input: String::new(),
+            compress_sources: Vec::new(),
             rename_input: String::new(),
             rename_cursor_pos: 0,
             rename_target: None,
-            filtered_indices: Vec::new(),
             selected_paths: Vec::new(),
             should_quit: false,
             config,
-            last_key: String::new(),
-            nav_history: Vec::new(),
+            normal_keys,
+            visual_keys,
+            pending_keys: Vec::new(),
+            pending_count: None,
             flash_copied_paths: Vec::new(),
             delete_targets: Vec::new(),
-            search_highlights: Vec::new(),
-            search_match_positions: HashMap::new(),
             error_message: None,
-            global_history: Vec::new(),
-            dir_sizes: HashMap::new(),
+            status_message: None,
             dir_size_rx,
             dir_size_tx,
+            trash_items: Vec::new(),
+            trash_state: ListState::default(),
+            journal: Journal::default(),
+            jobs: JobQueue::new(),
+            jobs_state: ListState::default(),
+            pending_paste: None,
+            show_preview,
+            preview: None,
+            preview_path: None,
+            preview_rx,
+            preview_tx,
+            miller_columns,
+            finder: None,
+            grep: None,
+            grep_input: String::new(),
+            bookmarks: Bookmarks::default(),
+            bookmarks_state: ListState::default(),
+            frecency: Frecency::default(),
+            jump_input: String::new(),
+            jump_matches: Vec::new(),
+            jump_state: ListState::default(),
+            choose_files: false,
+            chosen_files: Vec::new(),
+            cd_on_quit: false,
+            external: None,
+            open_with_path: None,
+            open_with_choices: Vec::new(),
+            open_with_state: ListState::default(),
+            watcher: None,
+            rename_plan: None,
+            rename_plan_state: ListState::default(),
+            pattern_rename: None,
         };
 
         app.load_directory()?;
-        app.list_state.select(Some(0));
+        app.tab.list_state.select(Some(0));
+        // Bad entries are skipped, the rest of the keymap still works. Every startup problem
+        // is reported together, so none hides another.
+        let mut startup_errors = keymap_errors;
+        match Bookmarks::load() {
+            Ok(bookmarks) => app.bookmarks = bookmarks,
+            Err(e) => startup_errors.push(format!("Could not load bookmarks: {}", e)),
+        }
+        if app.config.behavior.watch {
+            match DirWatcher::new() {
+                Ok(watcher) => app.watcher = Some(watcher),
+                Err(e) => startup_errors.push(format!("Could not watch directories: {}", e)),
+            }
+        }
+        match Frecency::load() {
+            Ok(frecency) => app.frecency = frecency,
+            Err(e) => startup_errors.push(format!("Could not load directory history: {}", e)),
+        }
+        if !startup_errors.is_empty() {
+            app.error_message = Some(startup_errors.join("; "));
+        }
+        // The start directory counts as a visit, like every directory changed to later
+        if app.tab.archive.is_none() {
+            app.frecency.add(&app.tab.current_dir);
+        }
         Ok(app)
     }
 
-    pub fn load_directory(&mut self) -> Result<()> {
-        self.files.clear();
-        self.dir_sizes.clear(); // Clear old directory sizes
-
-        // Read directory entries
-        for entry in fs::read_dir(&self.current_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            let metadata = entry.metadata()?;
-            let name = entry.file_name().to_string_lossy().to_string();
+    /// Loads `dir` into the active tab; filters only apply to the directory they were typed in
+    fn change_directory(&mut self, dir: PathBuf) -> Result<()> {
+        self.tab.current_dir = dir;
+        self.tab.filter_query.clear();
+        self.load_directory()?;
+        if self.tab.archive.is_none() {
+            self.frecency.add(&self.tab.current_dir);
+        }
+        Ok(())
+    }
 
-            let is_hidden = name.starts_with('.');
-            if !self.show_hidden && is_hidden {
-                continue;
-            }
+    pub fn load_directory(&mut self) -> Result<()> {
+        self.tab.load_directory(self.show_hidden, &self.sort_mode, self.sort_ascending)?;
+        self.update_filtered_indices();
+        // Contents may have changed, so previews are reloaded
+        self.preview_path = None;
+        self.load_parent_directory();
+        Ok(())
+    }
 
-            let is_executable = if cfg!(unix) {
-                metadata.permissions().mode() & 0o111 != 0
-            } else {
-                false
+    fn load_parent_directory(&mut self) {
+        self.tab.parent_files.clear();
+        if !self.miller_columns {
+            return;
+        }
+        if let Some(parent) = self.tab.current_dir.parent() {
+            let entries = match &self.tab.archive {
+                Some(archive) if parent.starts_with(&archive.path) => archive.read_dir(parent, self.show_hidden),
+                _ => read_entries(parent, self.show_hidden),
             };
-
-            let is_dir = metadata.is_dir();
-
-            self.files.push(FileEntry {
-                name: name.clone(),
-                path: path.clone(),
-                is_dir,
-                is_hidden,
-                is_symlink: metadata.is_symlink(),
-                is_executable: !is_dir && is_executable,
-                size: metadata.len(),
-                modified: metadata.modified().ok(),
-            });
-
-            // Mark directories for size calculation
-            if is_dir {
-                self.dir_sizes.insert(path, None);
+            // An unreadable parent just leaves the column empty
+            if let Ok(entries) = entries {
+                self.tab.parent_files = sort_entries(entries, &self.sort_mode, self.sort_ascending);
             }
         }
-
-        self.sort_files();
-        self.update_filtered_indices();
-        Ok(())
     }
 
     pub fn start_dir_size_calculation(&self) {
         // Get all directories that need size calculation
-        let dirs_to_calculate: Vec<PathBuf> = self.dir_sizes
+        let dirs_to_calculate: Vec<PathBuf> = self.tab.dir_sizes
             .iter()
+            .chain(self.other_pane.iter().flat_map(|pane| pane.dir_sizes.iter()))
             .filter_map(|(path, size)| if size.is_none() { Some(path.clone()) } else { None })
             .collect();
 
@@ -185,80 +389,370 @@ impl App {
     pub fn check_dir_size_updates(&mut self) {
         // Process all available directory size updates
         while let Ok((path, size)) = self.dir_size_rx.try_recv() {
-            self.dir_sizes.insert(path, Some(size));
+            // Sizes are only kept for listings that still show the directory
+            let tabs = std::iter::once(&mut self.tab)
+                .chain(self.tabs.iter_mut())
+                .chain(self.other_pane.iter_mut());
+            for tab in tabs {
+                if let Some(entry) = tab.dir_sizes.get_mut(&path) {
+                    *entry = Some(size);
+                }
+            }
         }
     }
 
-    pub fn sort_files(&mut self) {
-        // Separate directories and files
-        let (mut dirs, mut files): (Vec<_>, Vec<_>) =
-            self.files.iter().cloned().partition(|f| f.is_dir);
-
-        // Sort each group
-        match self.sort_mode {
-            SortMode::Name => {
-                if self.sort_ascending {
-                    dirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-                    files.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-                } else {
-                    dirs.sort_by(|a, b| b.name.to_lowercase().cmp(&a.name.to_lowercase()));
-                    files.sort_by(|a, b| b.name.to_lowercase().cmp(&a.name.to_lowercase()));
-                }
+    pub fn toggle_dual_pane(&mut self) -> Result<()> {
+        if self.other_pane.take().is_some() {
+            self.focus_left = true;
+            return Ok(());
+        }
+        let mut pane = Tab::new(self.tab.current_dir.clone());
+        pane.load_directory(self.show_hidden, &self.sort_mode, self.sort_ascending)?;
+        pane.list_state.select(Some(0));
+        self.other_pane = Some(pane);
+        self.focus_left = true;
+        self.start_dir_size_calculation();
+        Ok(())
+    }
+
+    /// Moves focus to the other pane in dual-pane mode
+    pub fn switch_pane(&mut self) -> Result<()> {
+        if let Some(pane) = self.other_pane.as_mut() {
+            std::mem::swap(&mut self.tab, pane);
+            self.focus_left = !self.focus_left;
+            self.refresh_tab()?;
+        }
+        Ok(())
+    }
+
+    /// Reloads the unfocused pane, e.g. after a job wrote into it
+    pub fn reload_other_pane(&mut self) -> Result<()> {
+        if let Some(pane) = self.other_pane.as_mut() {
+            let selected = pane.list_state.selected().unwrap_or(0);
+            pane.load_directory(self.show_hidden, &self.sort_mode, self.sort_ascending)?;
+            pane.list_state.select(Some(selected.min(pane.filtered_indices.len().saturating_sub(1))));
+        }
+        Ok(())
+    }
+
+    pub fn new_tab(&mut self) -> Result<()> {
+        let tab = Tab::new(self.tab.current_dir.clone());
+        self.tabs.insert(self.active_tab + 1, tab);
+        self.switch_tab(self.active_tab + 1)
+    }
+
+    pub fn close_tab(&mut self) -> Result<()> {
+        if self.tabs.len() <= 1 {
+            anyhow::bail!("Cannot close the last tab");
+        }
+        // The active slot is only a placeholder, so the active tab is simply replaced
+        self.tabs.remove(self.active_tab);
+        self.active_tab = self.active_tab.min(self.tabs.len() - 1);
+        std::mem::swap(&mut self.tab, &mut self.tabs[self.active_tab]);
+        self.refresh_tab()
+    }
+
+    pub fn switch_tab(&mut self, index: usize) -> Result<()> {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return Ok(());
+        }
+        std::mem::swap(&mut self.tab, &mut self.tabs[self.active_tab]);
+        self.active_tab = index;
+        std::mem::swap(&mut self.tab, &mut self.tabs[index]);
+        self.refresh_tab()
+    }
+
+    pub fn next_tab(&mut self) -> Result<()> {
+        self.switch_tab((self.active_tab + 1) % self.tabs.len())
+    }
+
+    pub fn previous_tab(&mut self) -> Result<()> {
+        let count = self.tabs.len();
+        self.switch_tab((self.active_tab + count - 1) % count)
+    }
+
+    /// Name of each tab's directory, in order
+    pub fn tab_titles(&self) -> Vec<String> {
+        (0..self.tabs.len())
+            .map(|i| {
+                let tab = if i == self.active_tab { &self.tab } else { &self.tabs[i] };
+                tab.current_dir
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| tab.current_dir.to_string_lossy().to_string())
+            })
+            .collect()
+    }
+
+    // The listing of a tab that was in the background may be stale
+    fn refresh_tab(&mut self) -> Result<()> {
+        self.preview_path = None;
+        if self.tab.list_state.selected().is_none() {
+            self.load_directory()?;
+            self.tab.list_state.select(Some(0));
+        } else {
+            self.reload_directory()?;
+        }
+        self.start_dir_size_calculation();
+        Ok(())
+    }
+
+    pub fn update_preview(&mut self) {
+        // Keep only the preview that matches the latest request
+        while let Ok((path, preview)) = self.preview_rx.try_recv() {
+            if self.preview_path.as_ref() == Some(&path) {
+                self.preview = Some((path, preview));
             }
-            SortMode::Size => {
-                dirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-                if self.sort_ascending {
-                    files.sort_by(|a, b| a.size.cmp(&b.size));
-                } else {
-                    files.sort_by(|a, b| b.size.cmp(&a.size));
-                }
+        }
+
+        // The miller layout shows the preview as its child column
+        if !self.show_preview && !self.miller_columns {
+            return;
+        }
+
+        let selected = self.get_selected_path();
+        if selected == self.preview_path {
+            return;
+        }
+        self.preview_path = selected.clone();
+        self.preview = None;
+
+        if let Some(path) = selected {
+            let tx = self.preview_tx.clone();
+            let show_hidden = self.show_hidden;
+            let sort_mode = self.sort_mode.clone();
+            let ascending = self.sort_ascending;
+            let archive = self.tab.archive.clone();
+            tokio::task::spawn_blocking(move || {
+                let preview = match archive {
+                    Some(archive) => crate::preview::load_archive_preview(&archive, &path, show_hidden, &sort_mode, ascending),
+                    None => crate::preview::load_preview(&path, show_hidden, &sort_mode, ascending),
+                };
+                let _ = tx.send((path, preview));
+            });
+        }
+    }
+
+    pub fn toggle_preview(&mut self) {
+        self.show_preview = !self.show_preview;
+        self.preview = None;
+        self.preview_path = None;
+    }
+
+    pub fn toggle_miller_columns(&mut self) {
+        self.miller_columns = !self.miller_columns;
+        self.preview = None;
+        self.preview_path = None;
+        self.load_parent_directory();
+    }
+
+    pub fn check_job_updates(&mut self) {
+        let finished = self.jobs.poll();
+        if finished.is_empty() {
+            return;
+        }
+
+        for job in finished {
+            let mut operations = Vec::new();
+            if !job.replaced.is_empty() {
+                operations.push(Operation::Delete(job.replaced));
             }
-            SortMode::Modified => {
-                dirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-                if self.sort_ascending {
-                    files.sort_by(|a, b| {
-                        a.modified
-                            .unwrap_or(SystemTime::UNIX_EPOCH)
-                            .cmp(&b.modified.unwrap_or(SystemTime::UNIX_EPOCH))
-                    });
-                } else {
-                    files.sort_by(|a, b| {
-                        b.modified
-                            .unwrap_or(SystemTime::UNIX_EPOCH)
-                            .cmp(&a.modified.unwrap_or(SystemTime::UNIX_EPOCH))
-                    });
+            if !job.completed.is_empty() {
+                operations.push(match job.kind {
+                    JobKind::Copy => Operation::Copy(job.completed),
+                    JobKind::Move => Operation::Move(job.completed),
+                    JobKind::Compress => Operation::Compress {
+                        archive: job.completed[0].1.clone(),
+                        sources: job.completed.into_iter().map(|(source, _)| source).collect(),
+                    },
+                    JobKind::Extract => Operation::Extract(job.completed),
+                });
+            }
+            match operations.len() {
+                0 => {}
+                1 => self.journal.record(operations.remove(0)),
+                _ => self.journal.record(Operation::Batch(operations)),
+            }
+
+            if let Some(error) = job.error {
+                self.error_message = Some(format!("{} failed: {}", job.description, error));
+            } else if job.cancelled {
+                self.status_message = Some(format!("Cancelled: {}", job.description));
+            } else {
+                self.status_message = Some(format!("Done: {}", job.description));
+            }
+        }
+
+        // Keep the jobs panel cursor on an existing job
+        let count = self.jobs.jobs.len();
+        if count == 0 {
+            self.jobs_state.select(None);
+        } else if self.jobs_state.selected().is_some_and(|i| i >= count) {
+            self.jobs_state.select(Some(count - 1));
+        }
+
+        if let Err(e) = self.reload_directory().and_then(|_| self.reload_other_pane()) {
+            self.error_message = Some(format!("Error loading directory: {}", e));
+        }
+        self.start_dir_size_calculation();
+    }
+
+    /// Reloads the current directory, keeping the cursor on the same entry when possible
+    pub fn reload_directory(&mut self) -> Result<()> {
+        let selected_path = self.get_selected_path();
+        let selected_index = self.tab.list_state.selected().unwrap_or(0);
+
+        self.load_directory()?;
+
+        let count = self.tab.filtered_indices.len();
+        let index = selected_path
+            .and_then(|path| {
+                self.tab.filtered_indices
+                    .iter()
+                    .position(|&i| self.tab.files[i].path == path)
+            })
+            .unwrap_or_else(|| selected_index.min(count.saturating_sub(1)));
+        self.tab.list_state.select(Some(index));
+        Ok(())
+    }
+
+    /// Reloads the listings whose directory changed on disk since the last call
+    pub fn check_watcher(&mut self) {
+        let Some(watcher) = self.watcher.as_mut() else {
+            return;
+        };
+        // Inside an archive, only the archive file itself can change
+        let dirs: Vec<&Path> = std::iter::once(&self.tab)
+            .chain(self.other_pane.as_ref())
+            .filter_map(|tab| match &tab.archive {
+                Some(archive) => archive.path.parent(),
+                None => Some(tab.current_dir.as_path()),
+            })
+            .collect();
+        watcher.watch(&dirs);
+
+        let changed = watcher.changed_paths();
+        if changed.is_empty() {
+            return;
+        }
+        let touches = |tab: &Tab| match &tab.archive {
+            Some(archive) => changed.contains(&archive.path),
+            None => changed.iter().any(|path| path == &tab.current_dir || path.parent() == Some(&tab.current_dir)),
+        };
+
+        if touches(&self.tab) {
+            self.refresh_current_dir(&changed);
+        }
+        if self.other_pane.as_ref().is_some_and(touches) {
+            self.refresh_other_pane(&changed);
+        }
+        self.start_dir_size_calculation();
+    }
+
+    // Reload after an outside change, keeping the cursor, marks and untouched directory sizes
+    fn refresh_current_dir(&mut self, changed: &HashSet<PathBuf>) {
+        // A directory deleted from under us falls back to its closest existing ancestor
+        if self.tab.is_gone() {
+            if let Some(dir) = self.tab.current_dir.ancestors().find(|dir| dir.is_dir()).map(Path::to_path_buf) {
+                self.clear_search();
+                if let Err(e) = self.change_directory(dir) {
+                    self.error_message = Some(format!("Error loading directory: {}", e));
                 }
+                self.tab.list_state.select(Some(0));
             }
+            return;
         }
 
-        // Combine: directories first, then files
-        self.files = dirs;
-        self.files.extend(files);
+        let old_sizes = std::mem::take(&mut self.tab.dir_sizes);
+        if let Err(e) = self.reload_directory() {
+            self.error_message = Some(format!("Error loading directory: {}", e));
+            return;
+        }
+        restore_dir_sizes(&mut self.tab.dir_sizes, &old_sizes, changed);
+        // Marks on entries that were removed would point at nothing
+        self.selected_paths.retain(|path| path.exists());
     }
 
-    pub fn update_filtered_indices(&mut self) {
-        if self.search_query.is_empty() {
-            self.filtered_indices = (0..self.files.len()).collect();
-        } else {
-            // Will be implemented with fuzzy search
-            self.filtered_indices = (0..self.files.len()).collect();
+    fn refresh_other_pane(&mut self, changed: &HashSet<PathBuf>) {
+        let Some(pane) = self.other_pane.as_mut() else {
+            return;
+        };
+        if pane.is_gone() {
+            if let Some(dir) = pane.current_dir.ancestors().find(|dir| dir.is_dir()) {
+                pane.current_dir = dir.to_path_buf();
+            }
         }
+
+        let selected_path = pane.list_state.selected()
+            .and_then(|i| pane.filtered_indices.get(i))
+            .map(|&i| pane.files[i].path.clone());
+        let selected_index = pane.list_state.selected().unwrap_or(0);
+        let old_sizes = std::mem::take(&mut pane.dir_sizes);
+        if let Err(e) = pane.load_directory(self.show_hidden, &self.sort_mode, self.sort_ascending) {
+            self.error_message = Some(format!("Error loading directory: {}", e));
+            return;
+        }
+        restore_dir_sizes(&mut pane.dir_sizes, &old_sizes, changed);
+
+        let count = pane.filtered_indices.len();
+        let index = selected_path
+            .and_then(|path| pane.filtered_indices.iter().position(|&i| pane.files[i].path == path))
+            .unwrap_or_else(|| selected_index.min(count.saturating_sub(1)));
+        pane.list_state.select(Some(index));
+    }
+
+    pub fn sort_files(&mut self) {
+        let files = std::mem::take(&mut self.tab.files);
+        self.tab.files = sort_entries(files, &self.sort_mode, self.sort_ascending);
+        let parent_files = std::mem::take(&mut self.tab.parent_files);
+        self.tab.parent_files = sort_entries(parent_files, &self.sort_mode, self.sort_ascending);
+        // Indices into the listing changed, and ties in the filter follow the sort order
+        self.update_filtered_indices();
+        // Directory previews follow the sort order too
+        self.preview_path = None;
+    }
+
+    pub fn update_filtered_indices(&mut self) {
+        self.tab.update_filtered_indices();
+        // Highlights refer to the visible entries, so they follow the listing
+        crate::fuzzy::refresh_search(self);
+    }
+
+    /// Applies `query` as the filter, keeping the cursor on the same entry when it still matches
+    pub fn set_filter(&mut self, query: String) {
+        let selected_path = self.get_selected_path();
+        self.tab.filter_query = query;
+        self.update_filtered_indices();
+
+        let index = selected_path
+            .and_then(|path| {
+                self.tab.filtered_indices
+                    .iter()
+                    .position(|&i| self.tab.files[i].path == path)
+            })
+            .unwrap_or(0);
+        let selected = if self.tab.filtered_indices.is_empty() { None } else { Some(index) };
+        self.tab.list_state.select(selected);
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.set_filter(String::new());
     }
 
     pub fn get_filtered_files(&self) -> Vec<&FileEntry> {
-        self.filtered_indices
+        self.tab.filtered_indices
             .iter()
-            .map(|&i| &self.files[i])
+            .map(|&i| &self.tab.files[i])
             .collect()
     }
 
     pub fn next(&mut self) {
-        let filtered_count = self.filtered_indices.len();
+        let filtered_count = self.tab.filtered_indices.len();
         if filtered_count == 0 {
             return;
         }
 
-        let i = match self.list_state.selected() {
+        let i = match self.tab.list_state.selected() {
             Some(i) => {
                 if i >= filtered_count - 1 {
                     filtered_count - 1 // Stay at bottom
@@ -268,16 +762,16 @@ impl App {
             }
             None => 0,
         };
-        self.list_state.select(Some(i));
+        self.tab.list_state.select(Some(i));
     }
 
     pub fn previous(&mut self) {
-        let filtered_count = self.filtered_indices.len();
+        let filtered_count = self.tab.filtered_indices.len();
         if filtered_count == 0 {
             return;
         }
 
-        let i = match self.list_state.selected() {
+        let i = match self.tab.list_state.selected() {
             Some(i) => {
                 if i == 0 {
                     0 // Stay at top
@@ -287,38 +781,34 @@ impl App {
             }
             None => 0,
         };
-        self.list_state.select(Some(i));
+        self.tab.list_state.select(Some(i));
+    }
+
+    /// Whether `path` can be entered: a directory, also one inside an archive, or an archive file
+    pub fn can_enter(&self, path: &Path) -> bool {
+        match &self.tab.archive {
+            Some(archive) => archive.is_dir(path),
+            None => path.is_dir() || (crate::archive::Format::of(path).is_some() && path.is_file()),
+        }
     }
 
     pub fn enter_directory(&mut self) -> Result<()> {
-        if let Some(selected) = self.list_state.selected() {
-            let next_path = {
-                let files = self.get_filtered_files();
-                if let Some(file) = files.get(selected) {
-                    if file.is_dir {
-                        Some(file.path.clone())
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            };
+        if let Some(selected) = self.tab.list_state.selected() {
+            let next_path = self.get_selected_path().filter(|path| self.can_enter(path));
 
             if let Some(next_path) = next_path {
                 // Save current position to both histories
-                self.nav_history.push(NavigationHistory {
-                    path: self.current_dir.clone(),
+                self.tab.nav_history.push(NavigationHistory {
+                    path: self.tab.current_dir.clone(),
                     selected_index: selected,
                 });
-                self.global_history.push(NavigationHistory {
-                    path: self.current_dir.clone(),
+                self.tab.global_history.push(NavigationHistory {
+                    path: self.tab.current_dir.clone(),
                     selected_index: selected,
                 });
 
-                self.current_dir = next_path;
-                self.load_directory()?;
-                self.list_state.select(Some(0));
+                self.change_directory(next_path)?;
+                self.tab.list_state.select(Some(0));
                 // Clear search when entering directory
                 self.clear_search();
             }
@@ -327,57 +817,55 @@ impl App {
     }
 
     pub fn go_parent(&mut self) -> Result<()> {
-        let current_selected = self.list_state.selected().unwrap_or(0);
+        let current_selected = self.tab.list_state.selected().unwrap_or(0);
 
         // Try to restore from history first
-        if let Some(hist) = self.nav_history.pop() {
+        if let Some(hist) = self.tab.nav_history.pop() {
             // Push current location to global history before navigating
-            self.global_history.push(NavigationHistory {
-                path: self.current_dir.clone(),
+            self.tab.global_history.push(NavigationHistory {
+                path: self.tab.current_dir.clone(),
                 selected_index: current_selected,
             });
 
-            self.current_dir = hist.path.clone();
-            self.load_directory()?;
+            self.change_directory(hist.path.clone())?;
 
             // Find the folder we came from and select it
-            let target_index = self.files.iter().position(|f| {
-                self.current_dir.join(&f.name) == self.nav_history.last()
+            let target_index = self.tab.files.iter().position(|f| {
+                self.tab.current_dir.join(&f.name) == self.tab.nav_history.last()
                     .map(|h| h.path.clone())
                     .unwrap_or_else(|| {
                         // If history is empty, find the folder that matches our previous dir
-                        if let Some(parent) = self.current_dir.parent() {
-                            parent.join(self.current_dir.file_name().unwrap_or_default())
+                        if let Some(parent) = self.tab.current_dir.parent() {
+                            parent.join(self.tab.current_dir.file_name().unwrap_or_default())
                         } else {
-                            self.current_dir.clone()
+                            self.tab.current_dir.clone()
                         }
                     })
-            }).unwrap_or(hist.selected_index.min(self.files.len().saturating_sub(1)));
+            }).unwrap_or(hist.selected_index.min(self.tab.files.len().saturating_sub(1)));
 
-            self.list_state.select(Some(target_index));
+            self.tab.list_state.select(Some(target_index));
             // Clear search when going to parent
             self.clear_search();
-        } else if let Some(parent) = self.current_dir.parent() {
+        } else if let Some(parent) = self.tab.current_dir.parent() {
             // Push current location to global history before navigating
-            self.global_history.push(NavigationHistory {
-                path: self.current_dir.clone(),
+            self.tab.global_history.push(NavigationHistory {
+                path: self.tab.current_dir.clone(),
                 selected_index: current_selected,
             });
 
-            let old_dir_name = self.current_dir.file_name()
+            let old_dir_name = self.tab.current_dir.file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("")
                 .to_string();
 
-            self.current_dir = parent.to_path_buf();
-            self.load_directory()?;
+            self.change_directory(parent.to_path_buf())?;
 
             // Find and select the directory we just came from
-            let target_index = self.files.iter()
+            let target_index = self.tab.files.iter()
                 .position(|f| f.name == old_dir_name)
                 .unwrap_or(0);
 
-            self.list_state.select(Some(target_index));
+            self.tab.list_state.select(Some(target_index));
             // Clear search when going to parent
             self.clear_search();
         }
@@ -391,28 +879,524 @@ impl App {
     }
 
     pub fn get_selected_path(&self) -> Option<PathBuf> {
-        self.list_state.selected().and_then(|i| {
+        self.tab.list_state.selected().and_then(|i| {
             self.get_filtered_files()
                 .get(i)
                 .map(|f| f.path.clone())
         })
     }
 
+    /// Paths of `count` entries starting at the cursor, in list order
+    pub fn paths_from_cursor(&self, count: usize) -> Vec<PathBuf> {
+        let Some(start) = self.tab.list_state.selected() else {
+            return Vec::new();
+        };
+        self.tab.filtered_indices
+            .iter()
+            .skip(start)
+            .take(count.max(1))
+            .map(|&i| self.tab.files[i].path.clone())
+            .collect()
+    }
+
     pub fn go_back_in_history(&mut self) -> Result<()> {
-        if let Some(hist) = self.global_history.pop() {
-            self.current_dir = hist.path;
-            self.load_directory()?;
-            self.list_state.select(Some(hist.selected_index.min(self.files.len().saturating_sub(1))));
+        if let Some(hist) = self.tab.global_history.pop() {
+            self.change_directory(hist.path)?;
+            self.tab.list_state.select(Some(hist.selected_index.min(self.tab.files.len().saturating_sub(1))));
             // Clear search when navigating
             self.clear_search();
         }
         Ok(())
     }
 
+    /// Navigates to `dir`, optionally placing the cursor on `select`
+    pub fn jump_to(&mut self, dir: PathBuf, select: Option<&Path>) -> Result<()> {
+        // Push current location to global history before jumping
+        self.tab.global_history.push(NavigationHistory {
+            path: self.tab.current_dir.clone(),
+            selected_index: self.tab.list_state.selected().unwrap_or(0),
+        });
+
+        // Clear search when jumping to a different directory
+        self.clear_search();
+        self.change_directory(dir)?;
+
+        self.tab.list_state.select(Some(0));
+        if let Some(path) = select {
+            self.select_path(path);
+        }
+        self.start_dir_size_calculation();
+        Ok(())
+    }
+
+    /// Opens `path` with `opener`, or with the system default application when None
+    pub fn open_path(&mut self, path: PathBuf, opener: Option<Opener>) {
+        let result = match opener {
+            Some(opener) if opener.terminal => {
+                self.external = Some(External::Open(opener, path));
+                Ok(())
+            }
+            Some(opener) => crate::opener::spawn_detached(&opener, &path),
+            None => crate::file_ops::open_file(&path),
+        };
+        match result {
+            Ok(()) => self.error_message = None,
+            Err(e) => self.error_message = Some(format!("Error opening file: {}", e)),
+        }
+    }
+
+    /// The first configured opener for `path`, if any
+    pub fn default_opener(&self, path: &Path) -> Option<Opener> {
+        crate::opener::matching(&self.config.openers, path).first().map(|o| (*o).clone())
+    }
+
+    /// Lists every opener for the file under the cursor, followed by the system default
+    pub fn open_with_menu(&mut self) {
+        let Some(path) = self.get_selected_path().filter(|p| !p.is_dir()) else {
+            return;
+        };
+        self.open_with_choices = crate::opener::matching(&self.config.openers, &path)
+            .into_iter()
+            .map(|opener| Some(opener.clone()))
+            .chain(std::iter::once(None))
+            .collect();
+        self.open_with_path = Some(path);
+        self.open_with_state.select(Some(0));
+        self.mode = Mode::OpenWith;
+    }
+
+    /// The marked entries of the current directory in list order, or all visible ones
+    fn rename_targets(&self) -> Vec<PathBuf> {
+        let visible = self.get_filtered_files();
+        let marked: Vec<PathBuf> = visible
+            .iter()
+            .filter(|file| self.selected_paths.contains(&file.path))
+            .map(|file| file.path.clone())
+            .collect();
+        if marked.is_empty() {
+            visible.iter().map(|file| file.path.clone()).collect()
+        } else {
+            marked
+        }
+    }
+
+    /// Writes the rename targets to a temporary file for renaming in the editor
+    pub fn start_bulk_rename(&mut self) {
+        let originals = self.rename_targets();
+        if originals.is_empty() {
+            return;
+        }
+
+        let names: Vec<String> = originals
+            .iter()
+            .map(|path| path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default())
+            .collect();
+        if names.iter().any(|name| name.contains('\n')) {
+            self.error_message = Some(String::from("Cannot bulk rename names that contain newlines"));
+            return;
+        }
+
+        let mut content = names.join("\n");
+        content.push('\n');
+        let written = crate::file_ops::create_temp_file("jumper-rename", ".txt")
+            .and_then(|(file, mut handle)| Ok(handle.write_all(content.as_bytes()).map(|_| file)?));
+        match written {
+            Ok(file) => self.external = Some(External::BulkRename { file, originals }),
+            Err(e) => self.error_message = Some(format!("Could not write the list of names: {}", e)),
+        }
+    }
+
+    /// Reads the edited names back and asks for confirmation of the resulting renames
+    pub fn finish_bulk_rename(&mut self, file: &Path, originals: Vec<PathBuf>) {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                self.error_message = Some(format!("Could not read {}: {}", file.display(), e));
+                return;
+            }
+        };
+        // Editors often leave blank lines at the end
+        let mut names: Vec<String> = content.lines().map(String::from).collect();
+        while names.last().is_some_and(|name| name.is_empty()) {
+            names.pop();
+        }
+
+        match RenamePlan::new(&originals, &names) {
+            Ok(plan) if plan.is_empty() => {
+                self.status_message = Some(String::from("No names changed"));
+            }
+            Ok(plan) => {
+                self.rename_plan = Some(plan);
+                self.rename_plan_state.select(Some(0));
+                self.mode = Mode::RenameConfirm;
+            }
+            Err(e) => self.error_message = Some(format!("Bulk rename cancelled: {}", e)),
+        }
+    }
+
+    /// Opens the regex / template rename with a live preview of the rename targets
+    pub fn start_pattern_rename(&mut self) {
+        let originals = self.rename_targets();
+        if originals.is_empty() {
+            return;
+        }
+        let existing = self.tab.files.iter().map(|file| file.name.clone()).collect();
+        self.pattern_rename = Some(PatternRename::new(originals, existing));
+        self.mode = Mode::PatternRename;
+    }
+
+    /// Applies the previewed names as one undoable step, or explains why they can't be
+    pub fn apply_pattern_rename(&mut self) {
+        let Some(pattern) = &self.pattern_rename else {
+            return;
+        };
+        match pattern.plan() {
+            Ok(plan) if plan.is_empty() => {
+                self.pattern_rename = None;
+                self.mode = Mode::Normal;
+                self.status_message = Some(String::from("No names changed"));
+            }
+            Ok(plan) => {
+                self.pattern_rename = None;
+                self.rename_plan = Some(plan);
+                self.apply_rename_plan();
+            }
+            Err(e) => self.error_message = Some(e.to_string()),
+        }
+    }
+
+    /// Applies the confirmed rename plan as one undoable step
+    pub fn apply_rename_plan(&mut self) {
+        self.mode = Mode::Normal;
+        let Some(plan) = self.rename_plan.take() else {
+            return;
+        };
+        if let Err(e) = plan.apply() {
+            self.error_message = Some(format!("{} (nothing was renamed)", e));
+            return;
+        }
+
+        // Marks follow the entries to their new names
+        for path in &mut self.selected_paths {
+            if let Some((_, to)) = plan.renames.iter().find(|(from, _)| from == path) {
+                *path = to.clone();
+            }
+        }
+        self.status_message = Some(format!("Renamed {} item(s)", plan.renames.len()));
+        self.journal.record(Operation::BulkRename(plan));
+        if let Err(e) = self.reload_directory() {
+            self.error_message = Some(format!("Error loading directory: {}", e));
+        }
+        self.start_dir_size_calculation();
+    }
+
+    /// Asks for the name of the archive to pack `sources` into, suggesting one after them
+    pub fn start_compress(&mut self, sources: Vec<PathBuf>) {
+        if sources.is_empty() {
+            return;
+        }
+        let base = match sources.as_slice() {
+            [source] if source.is_dir() => source.file_name(),
+            [source] => source.file_stem(),
+            _ => self.tab.current_dir.file_name(),
+        };
+        let base = base.map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| String::from("archive"));
+        self.compress_input = format!("{}.zip", base);
+        self.compress_sources = sources;
+        self.mode = Mode::Compress;
+    }
+
+    /// Starts packing the sources into the archive named in the prompt
+    pub fn finish_compress(&mut self) {
+        self.mode = Mode::Normal;
+        let sources = std::mem::take(&mut self.compress_sources);
+        let name = std::mem::take(&mut self.compress_input);
+        if name.is_empty() {
+            return;
+        }
+        let archive = self.tab.current_dir.join(name);
+        self.error_message = None;
+        crate::file_ops::compress_items(self, &sources, &archive);
+        if self.error_message.is_none() {
+            self.selected_paths.clear();
+        }
+    }
+
+    /// Moves the cursor onto `path` if it is listed in the current directory
+    pub fn select_path(&mut self, path: &Path) {
+        if let Some(index) = self.tab.filtered_indices
+            .iter()
+            .position(|&i| self.tab.files[i].path == path)
+        {
+            self.tab.list_state.select(Some(index));
+        }
+    }
+
+    /// Picks the marked files, or `path` when nothing is marked, and quits (--choose-files)
+    pub fn choose(&mut self, path: PathBuf) {
+        self.chosen_files = if self.selected_paths.is_empty() {
+            vec![path]
+        } else {
+            self.selected_paths.clone()
+        };
+        self.should_quit = true;
+    }
+
+    pub fn open_finder(&mut self) {
+        self.finder = Some(Finder::start(self.tab.current_dir.clone(), self.show_hidden));
+        self.mode = Mode::Finder;
+    }
+
+    pub fn close_finder(&mut self) {
+        // Dropping the finder stops the background walk
+        self.finder = None;
+        self.mode = Mode::Normal;
+    }
+
+    pub fn check_finder_updates(&mut self) {
+        if let Some(finder) = &mut self.finder {
+            finder.check_updates();
+        }
+    }
+
+    pub fn open_jump(&mut self) {
+        self.jump_input.clear();
+        self.update_jump_matches();
+        self.mode = Mode::Jump;
+    }
+
+    /// Re-ranks the visited directories against `jump_input`
+    pub fn update_jump_matches(&mut self) {
+        self.jump_matches = self.frecency.query(&self.jump_input, Some(&self.tab.current_dir));
+        let selected = if self.jump_matches.is_empty() { None } else { Some(0) };
+        self.jump_state.select(selected);
+    }
+
+    /// Searches file contents below the current directory for `grep_input`
+    pub fn start_grep(&mut self) {
+        self.grep = Some(Grep::start(
+            self.tab.current_dir.clone(),
+            self.grep_input.clone(),
+            self.show_hidden,
+        ));
+        self.mode = Mode::Grep;
+    }
+
+    pub fn close_grep(&mut self) {
+        // Dropping the search stops the background task
+        self.grep = None;
+        self.mode = Mode::Normal;
+    }
+
+    pub fn check_grep_updates(&mut self) {
+        if let Some(grep) = &mut self.grep {
+            grep.check_updates();
+        }
+    }
+
+    /// Bookmarks the current directory under `key`
+    pub fn set_bookmark(&mut self, key: char) {
+        if self.tab.archive.is_some() {
+            self.error_message = Some(String::from("Directories inside archives cannot be bookmarked"));
+            return;
+        }
+        // Bookmarks are saved as TOML, which only holds UTF-8
+        if self.tab.current_dir.to_str().is_none() {
+            self.error_message = Some(format!(
+                "Cannot bookmark {}: its path is not valid UTF-8",
+                self.tab.current_dir.display()
+            ));
+            return;
+        }
+        let dir = self.tab.current_dir.clone();
+        match self.bookmarks.set(key, dir.clone()) {
+            Ok(()) => self.status_message = Some(format!("Bookmark '{}' -> {}", key, dir.display())),
+            Err(e) => self.error_message = Some(format!("Could not save bookmarks: {}", e)),
+        }
+    }
+
+    pub fn jump_to_bookmark(&mut self, key: char) -> Result<()> {
+        let Some(dir) = self.bookmarks.get(key).map(|p| p.to_path_buf()) else {
+            anyhow::bail!("No bookmark '{}'", key);
+        };
+        if !dir.is_dir() {
+            anyhow::bail!("Bookmarked directory no longer exists: {}", dir.display());
+        }
+        self.jump_to(dir, None)
+    }
+
+    pub fn open_bookmarks(&mut self) {
+        let selected = if self.bookmarks.is_empty() { None } else { Some(0) };
+        self.bookmarks_state.select(selected);
+        self.mode = Mode::Bookmarks;
+    }
+
+    pub fn open_trash(&mut self) -> Result<()> {
+        self.trash_items = crate::trash::list_items()?;
+        let selected = if self.trash_items.is_empty() { None } else { Some(0) };
+        self.trash_state.select(selected);
+        self.mode = Mode::Trash;
+        Ok(())
+    }
+
+    /// Moves the cursor to the next (or previous) search match in list order, wrapping around
+    pub fn jump_to_match(&mut self, forward: bool) {
+        let positions = self.match_positions_in_list();
+        if positions.is_empty() {
+            if !self.tab.search_query.is_empty() {
+                self.error_message = Some(format!("Pattern not found: {}", self.tab.search_query));
+            }
+            return;
+        }
+
+        let cursor = self.tab.list_state.selected().unwrap_or(0);
+        let target = if forward {
+            positions.iter().find(|&&p| p > cursor)
+        } else {
+            positions.iter().rev().find(|&&p| p < cursor)
+        };
+        let target = match target {
+            Some(&position) => position,
+            None if forward => {
+                self.status_message = Some(String::from("Search wrapped to top"));
+                positions[0]
+            }
+            None => {
+                self.status_message = Some(String::from("Search wrapped to bottom"));
+                positions[positions.len() - 1]
+            }
+        };
+        self.tab.list_state.select(Some(target));
+    }
+
+    /// Searches for the name of the entry under the cursor
+    pub fn search_current_name(&mut self) {
+        let Some(name) = self.tab.list_state.selected()
+            .and_then(|i| self.tab.filtered_indices.get(i))
+            .map(|&i| self.tab.files[i].name.clone())
+        else {
+            return;
+        };
+        self.tab.search_query = name;
+        // The cursor already sits on a match, n / N continue from here
+        crate::fuzzy::refresh_search(self);
+    }
+
+    /// The 1-based number of the match under the cursor and the total number of matches
+    pub fn current_match(&self) -> Option<(usize, usize)> {
+        let positions = self.match_positions_in_list();
+        let cursor = self.tab.list_state.selected()?;
+        let index = positions.iter().position(|&p| p == cursor)?;
+        Some((index + 1, positions.len()))
+    }
+
+    // List positions of the search matches, in list order
+    fn match_positions_in_list(&self) -> Vec<usize> {
+        self.tab.filtered_indices
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| self.tab.search_match_positions.contains_key(i))
+            .map(|(position, _)| position)
+            .collect()
+    }
+
     pub fn clear_search(&mut self) {
-        self.search_query.clear();
-        self.search_highlights.clear();
-        self.search_match_positions.clear();
+        self.tab.search_query.clear();
+        self.tab.search_highlights.clear();
+        self.tab.search_match_positions.clear();
+    }
+}
+
+/// Reads the entries of `dir`, skipping hidden ones unless `show_hidden` is set
+pub fn read_entries(dir: &Path, show_hidden: bool) -> Result<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+
+    // Read directory entries
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let is_hidden = name.starts_with('.');
+        if !show_hidden && is_hidden {
+            continue;
+        }
+
+        let is_executable = if cfg!(unix) {
+            metadata.permissions().mode() & 0o111 != 0
+        } else {
+            false
+        };
+
+        let is_dir = metadata.is_dir();
+
+        entries.push(FileEntry {
+            name,
+            path,
+            is_dir,
+            is_hidden,
+            is_symlink: metadata.is_symlink(),
+            is_executable: !is_dir && is_executable,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        });
+    }
+
+    Ok(entries)
+}
+
+pub fn sort_entries(entries: Vec<FileEntry>, sort_mode: &SortMode, ascending: bool) -> Vec<FileEntry> {
+    // Separate directories and files
+    let (mut dirs, mut files): (Vec<_>, Vec<_>) =
+        entries.into_iter().partition(|f| f.is_dir);
+
+    // Sort each group
+    match sort_mode {
+        SortMode::Name => {
+            if ascending {
+                dirs.sort_by_key(|a| a.name.to_lowercase());
+                files.sort_by_key(|a| a.name.to_lowercase());
+            } else {
+                dirs.sort_by_key(|b| std::cmp::Reverse(b.name.to_lowercase()));
+                files.sort_by_key(|b| std::cmp::Reverse(b.name.to_lowercase()));
+            }
+        }
+        SortMode::Size => {
+            dirs.sort_by_key(|a| a.name.to_lowercase());
+            if ascending {
+                files.sort_by_key(|a| a.size);
+            } else {
+                files.sort_by_key(|b| std::cmp::Reverse(b.size));
+            }
+        }
+        SortMode::Modified => {
+            dirs.sort_by_key(|a| a.name.to_lowercase());
+            if ascending {
+                files.sort_by_key(|a| a.modified.unwrap_or(SystemTime::UNIX_EPOCH));
+            } else {
+                files.sort_by_key(|b| std::cmp::Reverse(b.modified.unwrap_or(SystemTime::UNIX_EPOCH)));
+            }
+        }
+    }
+
+    // Combine: directories first, then files
+    dirs.extend(files);
+    dirs
+}
+
+// Keeps the known sizes of directories that were not among the changed paths
+fn restore_dir_sizes(
+    sizes: &mut HashMap<PathBuf, Option<u64>>,
+    old_sizes: &HashMap<PathBuf, Option<u64>>,
+    changed: &HashSet<PathBuf>,
+) {
+    for (path, size) in sizes.iter_mut() {
+        if let Some(Some(old)) = old_sizes.get(path) {
+            if !changed.contains(path) {
+                *size = Some(*old);
+            }
+        }
     }
 }
 
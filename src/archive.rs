@@ -0,0 +1,607 @@
+use crate::app::FileEntry;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+const BUFFER_SIZE: usize = 256 * 1024;
+// Previews of compressed tar members decompress everything before them, so they stop here
+const COMPRESSED_PREVIEW_LIMIT: u64 = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+impl Format {
+    /// The archive format of `path`, judged by its name
+    pub fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Format::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Format::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(Format::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+/// The name of an archive without its extension, e.g. `photos` for `photos.tar.gz`
+pub fn stem(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    [".tar.gz", ".tar.zst", ".tgz", ".tzst", ".zip", ".tar"]
+        .iter()
+        .find_map(|ext| {
+            let split = name.len().checked_sub(ext.len())?;
+            let suffix = name.get(split..)?;
+            suffix.eq_ignore_ascii_case(ext).then(|| name[..split].to_string())
+        })
+        .filter(|stem| !stem.is_empty())
+}
+
+/// The archive file containing `path` when it points inside one, e.g. `/x/a.zip/dir`
+pub fn locate(path: &Path) -> Option<PathBuf> {
+    for ancestor in path.ancestors() {
+        match fs::metadata(ancestor) {
+            Ok(metadata) if metadata.is_file() && Format::of(ancestor).is_some() => {
+                return Some(ancestor.to_path_buf());
+            }
+            Ok(_) => return None,
+            Err(_) => continue,
+        }
+    }
+    None
+}
+
+/// Like `locate`, but None for the archive file itself, which is copied and moved as a file
+pub fn locate_entry(path: &Path) -> Option<PathBuf> {
+    locate(path).filter(|archive| archive != path)
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub path: PathBuf, // relative to the archive root
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64, // for a directory, the total size of the files below it
+    pub mode: u32,
+    pub modified: Option<SystemTime>,
+    index: Option<usize>, // position in a zip; None for directories that are only implied
+    offset: u64,          // where a tar member's data starts in the (decompressed) tarball
+    files: u64,           // number of files below a directory
+}
+
+/// The index of an archive, browsed like a read-only directory tree below `path`
+#[derive(Debug)]
+pub struct Archive {
+    pub path: PathBuf,
+    pub format: Format,
+    modified: Option<SystemTime>,
+    entries: Vec<ArchiveEntry>, // sorted by path
+    totals: (u64, u64),         // files and bytes in the whole archive
+}
+
+impl Archive {
+    /// Reads the list of entries; compressed tarballs are decompressed once to do so
+    pub fn open(path: &Path) -> Result<Self> {
+        let format = Format::of(path).ok_or_else(|| anyhow::anyhow!("{} is not an archive", path.display()))?;
+        let mut entries = BTreeMap::new();
+
+        match format {
+            Format::Zip => {
+                let mut zip = zip::ZipArchive::new(BufReader::new(fs::File::open(path)?))?;
+                for index in 0..zip.len() {
+                    let file = zip.by_index_raw(index)?;
+                    let Some(entry_path) = clean_path(Path::new(file.name())) else {
+                        continue;
+                    };
+                    entries.insert(entry_path.clone(), ArchiveEntry {
+                        path: entry_path,
+                        is_dir: file.is_dir(),
+                        is_symlink: file.is_symlink(),
+                        size: if file.is_dir() { 0 } else { file.size() },
+                        mode: file.unix_mode().unwrap_or(0),
+                        modified: file.last_modified().and_then(zip_time),
+                        index: Some(index),
+                        offset: 0,
+                        files: 0,
+                    });
+                }
+            }
+            _ => {
+                let mut tar = open_tar(path, format)?;
+                for entry in tar.entries()? {
+                    let entry = entry?;
+                    let header = entry.header();
+                    let kind = header.entry_type();
+                    if !(kind.is_file() || kind.is_dir() || kind.is_symlink()) {
+                        continue;
+                    }
+                    let Some(entry_path) = clean_path(&entry.path()?) else {
+                        continue;
+                    };
+                    entries.insert(entry_path.clone(), ArchiveEntry {
+                        path: entry_path,
+                        is_dir: kind.is_dir(),
+                        is_symlink: kind.is_symlink(),
+                        size: if kind.is_file() { header.size()? } else { 0 },
+                        mode: header.mode().unwrap_or(0),
+                        modified: header.mtime().ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+                        index: None,
+                        offset: entry.raw_file_position(),
+                        files: 0,
+                    });
+                }
+            }
+        }
+
+        // Archives often leave out the entries for parent directories
+        let implied: Vec<PathBuf> = entries
+            .keys()
+            .flat_map(|path| path.ancestors().skip(1))
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect();
+        for dir in implied {
+            entries.entry(dir.clone()).or_insert(ArchiveEntry {
+                path: dir,
+                is_dir: true,
+                is_symlink: false,
+                size: 0,
+                mode: 0,
+                modified: None,
+                index: None,
+                offset: 0,
+                files: 0,
+            });
+        }
+
+        // Directory sizes are summed once here, so listings do not go over every entry again
+        let files: Vec<(PathBuf, u64)> = entries
+            .values()
+            .filter(|entry| !entry.is_dir && !entry.is_symlink)
+            .map(|entry| (entry.path.clone(), entry.size))
+            .collect();
+        let mut totals = (0, 0);
+        for (file, size) in files {
+            totals = (totals.0 + 1, totals.1 + size);
+            for dir in file.ancestors().skip(1) {
+                if let Some(entry) = entries.get_mut(dir).filter(|entry| entry.is_dir) {
+                    entry.files += 1;
+                    entry.size += size;
+                }
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            modified: fs::metadata(path).and_then(|m| m.modified()).ok(),
+            entries: entries.into_values().collect(),
+            totals,
+        })
+    }
+
+    /// Whether the archive file changed since it was read
+    pub fn is_stale(&self) -> bool {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok() != self.modified
+    }
+
+    // `path` below the archive file, relative to the archive root
+    fn inner<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.path).unwrap_or(path)
+    }
+
+    pub fn entry(&self, path: &Path) -> Option<&ArchiveEntry> {
+        let inner = self.inner(path);
+        self.entries
+            .binary_search_by(|entry| entry.path.as_path().cmp(inner))
+            .ok()
+            .map(|index| &self.entries[index])
+    }
+
+    pub fn is_dir(&self, path: &Path) -> bool {
+        self.inner(path).as_os_str().is_empty() || self.entry(path).is_some_and(|entry| entry.is_dir)
+    }
+
+    // Entries at or below `path`
+    fn below<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a ArchiveEntry> {
+        let inner = self.inner(path);
+        self.entries.iter().filter(move |entry| entry.path.starts_with(inner))
+    }
+
+    /// Number of files and their total size at or below `path`
+    pub fn totals(&self, path: &Path) -> (u64, u64) {
+        if self.inner(path).as_os_str().is_empty() {
+            return self.totals;
+        }
+        match self.entry(path) {
+            Some(entry) if entry.is_dir => (entry.files, entry.size),
+            Some(entry) if !entry.is_symlink => (1, entry.size),
+            _ => (0, 0),
+        }
+    }
+
+    /// Lists the directory `path` the way `read_entries` lists a real one
+    pub fn read_dir(&self, path: &Path, show_hidden: bool) -> Result<Vec<FileEntry>> {
+        if !self.is_dir(path) {
+            anyhow::bail!("{} is not a directory in the archive", self.inner(path).display());
+        }
+        let inner = self.inner(path);
+
+        Ok(self.entries
+            .iter()
+            .filter(|entry| entry.path.parent() == Some(inner))
+            .filter_map(|entry| {
+                let name = entry.path.file_name()?.to_string_lossy().to_string();
+                let is_hidden = name.starts_with('.');
+                if !show_hidden && is_hidden {
+                    return None;
+                }
+                let path = self.path.join(&entry.path);
+                Some(FileEntry {
+                    size: entry.size,
+                    name,
+                    path,
+                    is_dir: entry.is_dir,
+                    is_hidden,
+                    is_symlink: entry.is_symlink,
+                    is_executable: !entry.is_dir && entry.mode & 0o111 != 0,
+                    modified: entry.modified,
+                })
+            })
+            .collect())
+    }
+
+    /// Up to `limit` bytes from the start of the file at `path`
+    pub fn read_head(&self, path: &Path, limit: usize) -> Result<Vec<u8>> {
+        let mut head = Vec::new();
+        let Some(entry) = self.entry(path).filter(|entry| !entry.is_dir) else {
+            anyhow::bail!("{} is not a file in the archive", self.inner(path).display());
+        };
+
+        match (self.format, entry.index) {
+            (Format::Zip, Some(index)) => {
+                let mut zip = zip::ZipArchive::new(BufReader::new(fs::File::open(&self.path)?))?;
+                zip.by_index(index)?.take(limit as u64).read_to_end(&mut head)?;
+            }
+            (Format::Tar, _) => {
+                // A plain tarball holds the data as is, so it is read in place
+                let mut file = fs::File::open(&self.path)?;
+                file.seek(SeekFrom::Start(entry.offset))?;
+                file.take(entry.size.min(limit as u64)).read_to_end(&mut head)?;
+            }
+            _ => {
+                if entry.offset > COMPRESSED_PREVIEW_LIMIT {
+                    anyhow::bail!("Too far into the compressed archive to preview");
+                }
+                let mut tar = open_tar(&self.path, self.format)?;
+                for found in tar.entries()? {
+                    let found = found?;
+                    if found.raw_file_position() == entry.offset {
+                        found.take(limit as u64).read_to_end(&mut head)?;
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(head)
+    }
+
+    /// Extracts `path` (a file or a whole directory) so that it ends up at `dest`.
+    /// `report` gets the bytes and files written since its last call and returns false
+    /// to cancel, in which case this returns Ok(false).
+    pub fn extract(&self, path: &Path, dest: &Path, mut report: impl FnMut(u64, u64) -> bool) -> Result<bool> {
+        let inner = self.inner(path).to_path_buf();
+        if !inner.as_os_str().is_empty() && self.entry(path).is_none() {
+            anyhow::bail!("{} is not in the archive", inner.display());
+        }
+        let target_of = |entry_path: &Path| match entry_path.strip_prefix(&inner) {
+            Ok(relative) if relative.as_os_str().is_empty() => dest.to_path_buf(),
+            Ok(relative) => dest.join(relative),
+            Err(_) => dest.to_path_buf(),
+        };
+
+        if self.is_dir(path) {
+            fs::create_dir_all(dest)?;
+        }
+        for entry in self.below(path).filter(|entry| entry.is_dir) {
+            fs::create_dir_all(target_of(&entry.path))?;
+        }
+
+        // Links are created last, so no file is ever written through one
+        let mut links = Vec::new();
+        match self.format {
+            Format::Zip => {
+                let mut zip = zip::ZipArchive::new(BufReader::new(fs::File::open(&self.path)?))?;
+                for entry in self.below(path).filter(|entry| !entry.is_dir) {
+                    let Some(index) = entry.index else {
+                        continue;
+                    };
+                    let mut file = zip.by_index(index)?;
+                    let target = target_of(&entry.path);
+                    if entry.is_symlink {
+                        let mut link = String::new();
+                        file.read_to_string(&mut link)?;
+                        links.push((PathBuf::from(link), target));
+                    } else if !write_file(&mut file, &target, entry.mode, &mut report)? {
+                        return Ok(false);
+                    }
+                }
+            }
+            _ => {
+                let mut tar = open_tar(&self.path, self.format)?;
+                for found in tar.entries()? {
+                    let mut found = found?;
+                    let kind = found.header().entry_type();
+                    let Some(entry_path) = clean_path(&found.path()?) else {
+                        continue;
+                    };
+                    if !entry_path.starts_with(&inner) || kind.is_dir() {
+                        continue;
+                    }
+                    let target = target_of(&entry_path);
+                    if kind.is_symlink() {
+                        if let Some(link) = found.link_name()? {
+                            links.push((link.into_owned(), target));
+                        }
+                    } else if kind.is_file() {
+                        let mode = found.header().mode().unwrap_or(0);
+                        if !write_file(&mut found, &target, mode, &mut report)? {
+                            return Ok(false);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (link, target) in links {
+            std::os::unix::fs::symlink(link, target)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Packs `sources` into a new archive at `dest`, each under its own name at the root;
+/// the format follows the name of `dest`. `report` works as for `Archive::extract`.
+/// On failure or cancellation the partial archive is left for the caller to remove.
+pub fn create(sources: &[PathBuf], dest: &Path, mut report: impl FnMut(u64, u64) -> bool) -> Result<bool> {
+    let format = Format::of(dest).ok_or_else(|| anyhow::anyhow!("{} is not an archive name", dest.display()))?;
+    check_names(sources)?;
+
+    // Every entry with the name it gets in the archive
+    let mut entries = Vec::new();
+    for source in sources {
+        let base = source.parent().unwrap_or(Path::new(""));
+        // A linked directory is stored as the link, not with its target's contents
+        for entry in WalkDir::new(source).follow_root_links(false) {
+            let entry = entry?;
+            let name = entry.path().strip_prefix(base)?.to_string_lossy().to_string();
+            entries.push((entry.path().to_path_buf(), name, entry.path().symlink_metadata()?));
+        }
+    }
+
+    let file = BufWriter::new(fs::OpenOptions::new().write(true).create_new(true).open(dest)?);
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipWriter::new(file);
+            if !append_zip(&mut zip, &entries, &mut report)? {
+                return Ok(false);
+            }
+            zip.finish()?.flush()?;
+        }
+        Format::Tar => {
+            let mut tar = tar::Builder::new(file);
+            if !append_tar(&mut tar, &entries, &mut report)? {
+                return Ok(false);
+            }
+            tar.into_inner()?.flush()?;
+        }
+        Format::TarGz => {
+            let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(file, flate2::Compression::default()));
+            if !append_tar(&mut tar, &entries, &mut report)? {
+                return Ok(false);
+            }
+            tar.into_inner()?.finish()?.flush()?;
+        }
+        Format::TarZst => {
+            let mut tar = tar::Builder::new(zstd::Encoder::new(file, 0)?);
+            if !append_tar(&mut tar, &entries, &mut report)? {
+                return Ok(false);
+            }
+            tar.into_inner()?.finish()?.flush()?;
+        }
+    }
+    Ok(true)
+}
+
+/// Fails when two of `sources` would get the same name at the root of an archive, such as
+/// files with one name from different directories
+pub fn check_names(sources: &[PathBuf]) -> Result<()> {
+    let mut seen = std::collections::HashSet::new();
+    for source in sources {
+        let name = source.file_name().unwrap_or(source.as_os_str());
+        if !seen.insert(name) {
+            anyhow::bail!("More than one marked item is named {}", name.to_string_lossy());
+        }
+    }
+    Ok(())
+}
+
+fn append_zip<W: Write + io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    entries: &[(PathBuf, String, fs::Metadata)],
+    report: &mut impl FnMut(u64, u64) -> bool,
+) -> Result<bool> {
+    for (path, name, metadata) in entries {
+        let mut options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(metadata.permissions().mode() & 0o777);
+        if let Some(time) = metadata.modified().ok().and_then(to_zip_time) {
+            options = options.last_modified_time(time);
+        }
+
+        if metadata.is_dir() {
+            zip.add_directory(format!("{}/", name), options)?;
+        } else if metadata.is_symlink() {
+            zip.add_symlink(name, fs::read_link(path)?.to_string_lossy(), options)?;
+        } else {
+            zip.start_file(name, options.large_file(metadata.len() >= u32::MAX as u64))?;
+            if !copy_counted(&mut fs::File::open(path)?, zip, report)? {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+fn append_tar<W: Write>(
+    tar: &mut tar::Builder<W>,
+    entries: &[(PathBuf, String, fs::Metadata)],
+    report: &mut impl FnMut(u64, u64) -> bool,
+) -> Result<bool> {
+    tar.follow_symlinks(false);
+    for (path, name, metadata) in entries {
+        if !metadata.is_file() {
+            tar.append_path_with_name(path, name)?;
+            continue;
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(metadata);
+        let mut reader = Counted {
+            inner: fs::File::open(path)?.take(metadata.len()),
+            report: &mut *report,
+            cancelled: false,
+        };
+        if let Err(e) = tar.append_data(&mut header, name, &mut reader) {
+            if reader.cancelled {
+                return Ok(false);
+            }
+            return Err(e.into());
+        }
+        if !report(0, 1) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// Copies one file's content, reporting as it goes; false when cancelled
+fn copy_counted(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    report: &mut impl FnMut(u64, u64) -> bool,
+) -> Result<bool> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        if !report(read as u64, 0) {
+            return Ok(false);
+        }
+    }
+    Ok(report(0, 1))
+}
+
+// Reports what is read through it; tar pulls the data itself, so cancelling fails the read
+struct Counted<'a, R, F> {
+    inner: R,
+    report: &'a mut F,
+    cancelled: bool,
+}
+
+impl<R: Read, F: FnMut(u64, u64) -> bool> Read for Counted<'_, R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read > 0 && !(self.report)(read as u64, 0) {
+            self.cancelled = true;
+            return Err(io::Error::other("cancelled"));
+        }
+        Ok(read)
+    }
+}
+
+fn open_tar(path: &Path, format: Format) -> Result<tar::Archive<Box<dyn Read>>> {
+    let file = BufReader::new(fs::File::open(path)?);
+    let reader: Box<dyn Read> = match format {
+        Format::TarGz => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        Format::TarZst => Box::new(zstd::Decoder::with_buffer(file)?),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+// A relative path without `.`, `..` or a root; None for entries that would escape the archive
+fn clean_path(path: &Path) -> Option<PathBuf> {
+    let mut clean = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if clean.as_os_str().is_empty() {
+        None
+    } else {
+        Some(clean)
+    }
+}
+
+// Zip timestamps are in local time
+fn zip_time(time: zip::DateTime) -> Option<SystemTime> {
+    let date = chrono::NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())?;
+    let local = date
+        .and_hms_opt(time.hour().into(), time.minute().into(), time.second().into())?
+        .and_local_timezone(chrono::Local)
+        .earliest()?;
+    Some(local.into())
+}
+
+// Zip timestamps are in local time and start in 1980
+fn to_zip_time(time: SystemTime) -> Option<zip::DateTime> {
+    use chrono::{Datelike, Timelike};
+    let local: chrono::DateTime<chrono::Local> = time.into();
+    zip::DateTime::from_date_and_time(
+        u16::try_from(local.year()).ok()?,
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .ok()
+}
+
+fn write_file(
+    reader: &mut impl Read,
+    target: &Path,
+    mode: u32,
+    report: &mut impl FnMut(u64, u64) -> bool,
+) -> Result<bool> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if !copy_counted(reader, &mut fs::File::create(target)?, report)? {
+        return Ok(false);
+    }
+    if mode & 0o777 != 0 {
+        fs::set_permissions(target, fs::Permissions::from_mode(mode & 0o777))?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories bookmarked at runtime, keyed by a single character
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct Bookmarks {
+    #[serde(default)]
+    pub bookmarks: BTreeMap<String, PathBuf>,
+}
+
+impl Bookmarks {
+    /// Loads the saved bookmarks; a missing file means there are none yet
+    pub fn load() -> Result<Self> {
+        let path = Self::get_state_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Applies `change` to the bookmarks on disk and saves them. The file is read again first,
+    /// so bookmarks set by other sessions are kept, and one that does not load is left alone.
+    fn update(&mut self, change: impl FnOnce(&mut BTreeMap<String, PathBuf>)) -> Result<()> {
+        let mut saved = Self::load()?;
+        change(&mut saved.bookmarks);
+        crate::file_ops::write_atomic(&Self::get_state_path()?, &toml::to_string_pretty(&saved)?)?;
+        *self = saved;
+        Ok(())
+    }
+
+    pub fn get(&self, key: char) -> Option<&Path> {
+        self.bookmarks.get(&key.to_string()).map(|p| p.as_path())
+    }
+
+    /// Sets `key` to `dir` and saves, replacing any previous bookmark on that key
+    pub fn set(&mut self, key: char, dir: PathBuf) -> Result<()> {
+        self.update(|bookmarks| {
+            bookmarks.insert(key.to_string(), dir);
+        })
+    }
+
+    pub fn remove(&mut self, key: &str) -> Result<()> {
+        self.update(|bookmarks| {
+            bookmarks.remove(key);
+        })
+    }
+
+    /// Bookmarks in key order, as shown in the bookmarks list
+    pub fn entries(&self) -> Vec<(&String, &PathBuf)> {
+        self.bookmarks.iter().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bookmarks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty()
+    }
+
+    fn get_state_path() -> Result<PathBuf> {
+        if let Some(proj_dirs) = ProjectDirs::from("", "", "jumper") {
+            Ok(proj_dirs.data_dir().join("bookmarks.toml"))
+        } else {
+            anyhow::bail!("Could not determine data directory")
+        }
+    }
+}
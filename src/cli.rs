@@ -0,0 +1,106 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+A blazing fast terminal file manager with vim-like keybindings
+
+Usage: jumper [OPTIONS] [PATH]
+       jumper init <SHELL>
+
+Commands:
+  init <SHELL>  Print the shell integration for bash, zsh, fish or nushell
+
+Arguments:
+  [PATH]  Directory to open; a file opens its directory with the file selected
+
+Options:
+      --choose-files <FILE>  Pick files: opening a file writes it (or the marked
+                             files) to FILE, one path per line, and quits
+      --choose-dir <FILE>    Write the last visited directory to FILE on exit
+      --print-last-dir       Print the last visited directory on exit
+      --cwd-file <FILE>      Write the current directory to FILE when quitting
+                             with the quit-and-cd key (used by `jumper init`)
+      --config <FILE>        Read the configuration from FILE
+      --query <TERM>...      Print the best frecency match for TERM and exit
+  -h, --help                 Print help
+  -V, --version              Print version";
+
+/// Options for an interactive session
+#[derive(Debug, Default)]
+pub struct Args {
+    pub path: Option<PathBuf>,
+    pub choose_files: Option<PathBuf>,
+    pub choose_dir: Option<PathBuf>,
+    pub cwd_file: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    pub print_last_dir: bool,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Run(Args),
+    Query(String),
+    Init(String),
+    Help,
+    Version,
+}
+
+/// Parses the arguments after the program name
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter().peekable();
+
+    if args.peek().map(String::as_str) == Some("init") {
+        args.next();
+        let shell = args.next().ok_or_else(|| anyhow::anyhow!("init requires a shell name"))?;
+        if let Some(extra) = args.next() {
+            anyhow::bail!("unexpected argument '{}'", extra);
+        }
+        return Ok(Command::Init(shell));
+    }
+
+    let mut options_done = false;
+
+    while let Some(arg) = args.next() {
+        if options_done || !arg.starts_with('-') || arg == "-" {
+            if parsed.path.is_some() {
+                anyhow::bail!("unexpected argument '{}'", arg);
+            }
+            parsed.path = Some(PathBuf::from(arg));
+            continue;
+        }
+
+        // Both `--opt value` and `--opt=value` are accepted
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        let mut value = |name: &str| -> Result<PathBuf> {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow::anyhow!("{} requires a value", name))
+        };
+
+        match name.as_str() {
+            "--" => options_done = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--choose-files" => parsed.choose_files = Some(value(&name)?),
+            "--choose-dir" => parsed.choose_dir = Some(value(&name)?),
+            "--config" => parsed.config = Some(value(&name)?),
+            "--cwd-file" => parsed.cwd_file = Some(value(&name)?),
+            "--print-last-dir" => parsed.print_last_dir = true,
+            "--query" => {
+                // Everything after --query is the search term
+                let mut terms: Vec<String> = inline_value.into_iter().collect();
+                terms.extend(args.by_ref());
+                return Ok(Command::Query(terms.join(" ")));
+            }
+            _ => anyhow::bail!("unknown option '{}'", arg),
+        }
+    }
+
+    Ok(Command::Run(parsed))
+}
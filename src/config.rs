@@ -1,12 +1,14 @@
+use crate::keymap::Action;
+use crate::opener::Opener;
 use anyhow::Result;
 use directories::ProjectDirs;
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct Config {
     #[serde(default)]
     pub colors: ColorScheme,
@@ -14,6 +16,8 @@ pub struct Config {
     pub keybindings: KeyBindings,
     #[serde(default)]
     pub behavior: Behavior,
+    #[serde(default, with = "crate::opener::table")]
+    pub openers: Vec<Opener>, // tried in order; the first match opens the file
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -38,6 +42,10 @@ pub struct KeyBindings {
     pub quick_jumps: HashMap<String, String>,
     #[serde(default = "default_history_back")]
     pub history_back: String,
+    #[serde(default)]
+    pub normal: HashMap<String, Action>, // key sequence -> action, on top of the defaults
+    #[serde(default)]
+    pub visual: HashMap<String, Action>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -50,6 +58,18 @@ pub struct Behavior {
     pub delete_confirmation: bool,
     #[serde(default = "default_flash_duration_ms")]
     pub flash_duration_ms: u64,
+    #[serde(default = "default_delete_mode")]
+    pub delete_mode: DeleteMode,
+    #[serde(default = "default_paste_conflict")]
+    pub paste_conflict: PasteConflict,
+    #[serde(default)]
+    pub preview: bool,
+    #[serde(default)]
+    pub miller_columns: bool,
+    #[serde(default = "default_watch")]
+    pub watch: bool, // reload listings when files change on disk
+    #[serde(default = "default_key_timeout_ms")]
+    pub key_timeout_ms: u64, // how long a partly typed key sequence waits for its next key
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -60,6 +80,24 @@ pub enum SortMode {
     Modified,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteMode {
+    Trash,
+    Permanent,
+}
+
+// What to do when a pasted item already exists in the destination
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteConflict {
+    Ask,
+    Overwrite,
+    Skip,
+    Rename,
+    Newer,
+}
+
 // Default values
 fn default_directory_color() -> Color {
     Color::Blue
@@ -97,6 +135,22 @@ fn default_flash_duration_ms() -> u64 {
     150
 }
 
+fn default_delete_mode() -> DeleteMode {
+    DeleteMode::Trash
+}
+
+fn default_paste_conflict() -> PasteConflict {
+    PasteConflict::Ask
+}
+
+fn default_watch() -> bool {
+    true
+}
+
+fn default_key_timeout_ms() -> u64 {
+    1000
+}
+
 fn default_history_back() -> String {
     "ctrl+o".to_string()
 }
@@ -131,6 +185,8 @@ impl Default for KeyBindings {
         Self {
             quick_jumps,
             history_back: default_history_back(),
+            normal: HashMap::new(),
+            visual: HashMap::new(),
         }
     }
 }
@@ -142,16 +198,12 @@ impl Default for Behavior {
             default_sort: default_sort(),
             delete_confirmation: default_delete_confirmation(),
             flash_duration_ms: default_flash_duration_ms(),
-        }
-    }
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            colors: ColorScheme::default(),
-            keybindings: KeyBindings::default(),
-            behavior: Behavior::default(),
+            delete_mode: default_delete_mode(),
+            paste_conflict: default_paste_conflict(),
+            preview: false,
+            miller_columns: false,
+            watch: default_watch(),
+            key_timeout_ms: default_key_timeout_ms(),
         }
     }
 }
@@ -172,6 +224,12 @@ impl Config {
         }
     }
 
+    /// Loads a config file given on the command line; unlike `load`, a missing file is an error
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
     pub fn save(&self) -> Result<()> {
         let config_path = Self::get_config_path()?;
 
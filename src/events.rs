@@ -1,154 +1,214 @@
-use crate::app::{App, ClipboardOperation, Mode};
+use crate::app::{App, ClipboardOperation, External, Mode};
+use crate::file_ops::{ConflictResolution, Operation};
 use crate::config::SortMode;
+use crate::keymap::{Action, KeyChord, Target};
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::path::PathBuf;
 
-fn matches_keybinding(key: &KeyEvent, binding: &str) -> bool {
-    let parts: Vec<&str> = binding.split('+').collect();
+// Upper bound for count prefixes like 5j
+const MAX_COUNT: usize = 99_999;
 
-    let mut has_ctrl = false;
-    let mut has_shift = false;
-    let mut has_alt = false;
-    let mut key_char = None;
-
-    for part in parts {
-        match part.to_lowercase().as_str() {
-            "ctrl" | "control" => has_ctrl = true,
-            "shift" => has_shift = true,
-            "alt" => has_alt = true,
-            c if c.len() == 1 => key_char = Some(c.chars().next().unwrap()),
-            _ => {}
-        }
-    }
-
-    if let Some(ch) = key_char {
-        if let KeyCode::Char(key_ch) = key.code {
-            let modifiers_match =
-                key.modifiers.contains(KeyModifiers::CONTROL) == has_ctrl &&
-                key.modifiers.contains(KeyModifiers::SHIFT) == has_shift &&
-                key.modifiers.contains(KeyModifiers::ALT) == has_alt;
-
-            return key_ch == ch && modifiers_match;
-        }
-    }
-
-    false
-}
+const ARCHIVE_OPEN_ERROR: &str = "Copy the file out of the archive to open it";
 
 pub async fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
     // Only handle Press events for consistency across platforms
     if key.kind != KeyEventKind::Press {
         return Ok(());
     }
+    handle_key(app, key)
+}
 
-    // Store last key for multi-key bindings
-    let current_key = format_key(&key);
-    let two_key_combo = format!("{}{}", app.last_key, current_key);
+fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    // Partly typed sequences only carry over between normal and visual key presses
+    if !matches!(app.mode, Mode::Normal | Mode::VisualMulti) {
+        app.pending_keys.clear();
+    }
 
     match app.mode {
-        Mode::Normal => handle_normal_mode(app, key, &two_key_combo)?,
-        Mode::VisualMulti => handle_visual_multi_mode(app, key, &two_key_combo)?,
+        Mode::Normal | Mode::VisualMulti => dispatch_keys(app, key)?,
         Mode::Search => handle_search_mode(app, key)?,
         Mode::SortMenu => handle_sort_menu(app, key)?,
         Mode::Create => handle_create_mode(app, key)?,
         Mode::Rename => handle_rename_mode(app, key)?,
         Mode::Help => handle_help_mode(app, key)?,
         Mode::DeleteConfirm => handle_delete_confirm_mode(app, key)?,
+        Mode::Trash => handle_trash_mode(app, key)?,
+        Mode::PurgeConfirm => handle_purge_confirm_mode(app, key)?,
+        Mode::Jobs => handle_jobs_mode(app, key)?,
+        Mode::PasteConflict => handle_paste_conflict_mode(app, key)?,
+        Mode::Finder => handle_finder_mode(app, key)?,
+        Mode::Jump => handle_jump_mode(app, key)?,
+        Mode::OpenWith => handle_open_with_mode(app, key)?,
+        Mode::RenameConfirm => handle_rename_confirm_mode(app, key)?,
+        Mode::PatternRename => handle_pattern_rename_mode(app, key)?,
+        Mode::Compress => handle_compress_mode(app, key)?,
+        Mode::Filter => handle_filter_mode(app, key)?,
+        Mode::GrepInput => handle_grep_input_mode(app, key)?,
+        Mode::Grep => handle_grep_mode(app, key)?,
+        Mode::BookmarkSet | Mode::BookmarkJump => handle_bookmark_key_mode(app, key)?,
+        Mode::Bookmarks => handle_bookmarks_mode(app, key)?,
     }
 
-    // Update last key
-    app.last_key = current_key;
-
     Ok(())
 }
 
-fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Result<()> {
-    // Check for history back keybinding first
-    if matches_keybinding(&key, &app.config.keybindings.history_back) {
-        if let Err(e) = app.go_back_in_history() {
-            app.error_message = Some(format!("Error going back in history: {}", e));
-        } else {
-            app.error_message = None;
-            app.start_dir_size_calculation();
+/// Resolves the keys typed so far against the normal or visual keymap and runs the bound action
+fn dispatch_keys(app: &mut App, key: KeyEvent) -> Result<()> {
+    let chord = KeyChord::from_event(&key);
+    let visual = app.mode == Mode::VisualMulti;
+    let keymap = if visual { &app.visual_keys } else { &app.normal_keys };
+
+    // Digits typed before a command form its count (0 only continues one, unless bound)
+    if let (KeyCode::Char(c @ '0'..='9'), true) = (chord.code, chord.modifiers.is_empty()) {
+        let starts_count = c != '0' && keymap.lookup(&[chord]).0.is_none();
+        if app.pending_keys.is_empty() && (app.pending_count.is_some() || starts_count) {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            let count = app.pending_count.unwrap_or(0) * 10 + digit;
+            app.pending_count = Some(count.min(MAX_COUNT));
+            return Ok(());
         }
+    }
+
+    app.pending_keys.push(chord);
+    let (target, is_prefix) = keymap.lookup(&app.pending_keys);
+    if target.is_none() && !is_prefix && app.pending_keys.len() > 1 {
+        // The sequence went nowhere: the binding typed before this key runs (y then j runs
+        // y), and this key starts over on its own, possibly in the mode that binding opened
+        app.pending_keys.pop();
+        run_pending_keys(app)?;
+        return handle_key(app, key);
+    }
+    if is_prefix {
+        // Wait for the next key even when the keys so far are bound too (y / yy). It decides
+        // which binding runs, or `run_pending_keys` does once `key_timeout_ms` passes.
         return Ok(());
     }
 
-    // Handle quick jumps (two-key combinations)
-    if let Some(path) = app.config.keybindings.quick_jumps.get(two_key_combo) {
-        let path_buf = std::path::PathBuf::from(path);
+    let target = target.cloned();
+    let count = app.pending_count.take();
+    app.pending_keys.clear();
+    match target {
+        Some(target) => run_target(app, target, count),
+        None => Ok(()),
+    }
+}
 
-        // Check if the path exists
-        if path_buf.exists() && path_buf.is_dir() {
-            // Push current location to global history before jumping
-            if let Some(current_selected) = app.list_state.selected() {
-                app.global_history.push(crate::app::NavigationHistory {
-                    path: app.current_dir.clone(),
-                    selected_index: current_selected,
-                });
-            }
+/// Runs the binding of the keys typed so far, if they have one, and starts a new sequence.
+/// Called when no key follows them within `key_timeout_ms`.
+pub fn run_pending_keys(app: &mut App) -> Result<()> {
+    let pending = std::mem::take(&mut app.pending_keys);
+    let keymap = if app.mode == Mode::VisualMulti { &app.visual_keys } else { &app.normal_keys };
+    match keymap.lookup(&pending).0.cloned() {
+        Some(target) => {
+            let count = app.pending_count.take();
+            run_target(app, target, count)
+        }
+        None => Ok(()),
+    }
+}
 
-            app.current_dir = path_buf;
-            if let Err(e) = app.load_directory() {
-                app.error_message = Some(format!("Error loading directory: {}", e));
-            } else {
-                app.list_state.select(Some(0));
-                app.error_message = None;
-                // Clear search when jumping to different directory
-                app.clear_search();
-                // Start calculating directory sizes
-                app.start_dir_size_calculation();
-            }
-        } else {
-            app.error_message = Some(format!("Path does not exist: {}", path));
+fn run_target(app: &mut App, target: Target, count: Option<usize>) -> Result<()> {
+    let visual = app.mode == Mode::VisualMulti;
+    if let Target::Action(action) = target {
+        if let Some(error) = archive_restriction(app, action) {
+            app.error_message = Some(String::from(error));
+            return Ok(());
         }
+    }
 
-        app.last_key.clear();
-        return Ok(());
+    match target {
+        Target::Action(action) if visual => handle_visual_action(app, action, count),
+        Target::Action(action) => handle_normal_action(app, action, count),
+        Target::Jump(path) if !visual => {
+            quick_jump(app, path);
+            Ok(())
+        }
+        _ => Ok(()),
     }
+}
 
-    // Handle gg (jump to top)
-    if two_key_combo == "gg" {
-        app.list_state.select(Some(0));
-        app.last_key.clear();
-        return Ok(());
+// Entries inside an archive can only be browsed and copied out
+fn archive_restriction(app: &App, action: Action) -> Option<&'static str> {
+    app.tab.archive.as_ref()?;
+    match action {
+        Action::Cut
+        | Action::Paste
+        | Action::MoveToOtherPane
+        | Action::Create
+        | Action::Rename
+        | Action::RenameFull
+        | Action::BulkRename
+        | Action::PatternRename
+        | Action::Compress
+        | Action::Extract
+        | Action::Delete => Some("Archives are read-only; copy entries out with y and p"),
+        Action::Edit | Action::OpenWith => Some(ARCHIVE_OPEN_ERROR),
+        Action::Find | Action::Grep => Some("Searching below a directory is not available inside archives"),
+        _ => None,
     }
+}
 
-    match (key.code, key.modifiers) {
+fn quick_jump(app: &mut App, path: PathBuf) {
+    // Check if the path exists
+    if path.is_dir() {
+        if let Err(e) = app.jump_to(path, None) {
+            app.error_message = Some(format!("Error loading directory: {}", e));
+        } else {
+            app.error_message = None;
+        }
+    } else {
+        app.error_message = Some(format!("Path does not exist: {}", path.display()));
+    }
+}
+
+fn handle_normal_action(app: &mut App, action: Action, count: Option<usize>) -> Result<()> {
+    let repeat = count.unwrap_or(1);
+
+    match action {
         // Quit
-        (KeyCode::Char('q'), KeyModifiers::NONE) => {
+        Action::Quit => {
+            app.should_quit = true;
+        }
+        Action::QuitAndCd => {
+            app.cd_on_quit = true;
             app.should_quit = true;
         }
 
         // Navigation
-        (KeyCode::Char('j'), KeyModifiers::NONE) | (KeyCode::Down, _) => {
-            app.next();
+        Action::MoveDown => {
+            for _ in 0..repeat {
+                app.next();
+            }
             app.error_message = None;
         }
-        (KeyCode::Char('k'), KeyModifiers::NONE) | (KeyCode::Up, _) => {
-            app.previous();
+        Action::MoveUp => {
+            for _ in 0..repeat {
+                app.previous();
+            }
             app.error_message = None;
         }
-        (KeyCode::Char('l'), KeyModifiers::NONE) | (KeyCode::Right, _) => {
+        Action::EnterDir => {
             if let Some(path) = app.get_selected_path() {
-                if path.is_dir() {
+                if app.can_enter(&path) {
                     if let Err(e) = app.enter_directory() {
                         app.error_message = Some(format!("Error entering directory: {}", e));
                     } else {
                         app.error_message = None;
                         app.start_dir_size_calculation();
                     }
+                } else if app.tab.archive.is_some() {
+                    app.error_message = Some(String::from(ARCHIVE_OPEN_ERROR));
+                } else if app.choose_files {
+                    app.choose(path);
                 } else {
-                    // Open file with default application
-                    if let Err(e) = crate::file_ops::open_file(&path) {
-                        app.error_message = Some(format!("Error opening file: {}", e));
-                    } else {
-                        app.error_message = None;
-                    }
+                    // The first matching opener from the config, else the default application
+                    let opener = app.default_opener(&path);
+                    app.open_path(path, opener);
                 }
             }
         }
-        (KeyCode::Char('h'), KeyModifiers::NONE) | (KeyCode::Left, _) => {
+        Action::GoParent => {
             if let Err(e) = app.go_parent() {
                 app.error_message = Some(format!("Error going to parent: {}", e));
             } else {
@@ -157,25 +217,102 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
             }
         }
 
-        // Jump to top (Shift+G for bottom is handled below)
-        (KeyCode::Char('G'), KeyModifiers::SHIFT) => {
-            // Jump to bottom
-            let count = app.filtered_indices.len();
-            if count > 0 {
-                app.list_state.select(Some(count - 1));
+        Action::HistoryBack => {
+            if let Err(e) = app.go_back_in_history() {
+                app.error_message = Some(format!("Error going back in history: {}", e));
+            } else {
+                app.error_message = None;
+                app.start_dir_size_calculation();
+            }
+        }
+
+        // Jump to top / bottom, or to line N with a count
+        Action::GoTop | Action::GoBottom => {
+            let total = app.tab.filtered_indices.len();
+            if total > 0 {
+                let line = match (count, action) {
+                    (Some(line), _) => line.clamp(1, total) - 1,
+                    (None, Action::GoTop) => 0,
+                    (None, _) => total - 1,
+                };
+                app.tab.list_state.select(Some(line));
             }
         }
 
         // Toggle hidden files
-        (KeyCode::Char('.'), KeyModifiers::NONE) => {
+        Action::ToggleHidden => {
             app.toggle_hidden()?;
             app.start_dir_size_calculation();
         }
 
+        // Tabs
+        Action::NewTab => {
+            if let Err(e) = app.new_tab() {
+                app.error_message = Some(format!("Error opening tab: {}", e));
+            }
+        }
+        Action::CloseTab => {
+            if let Err(e) = app.close_tab() {
+                app.error_message = Some(format!("{}", e));
+            }
+        }
+        Action::NextTab => {
+            if let Err(e) = app.next_tab() {
+                app.error_message = Some(format!("Error switching tab: {}", e));
+            }
+        }
+        Action::PreviousTab => {
+            if let Err(e) = app.previous_tab() {
+                app.error_message = Some(format!("Error switching tab: {}", e));
+            }
+        }
+
+        // Dual-pane mode
+        Action::ToggleDualPane => {
+            if let Err(e) = app.toggle_dual_pane() {
+                app.error_message = Some(format!("Error opening pane: {}", e));
+            }
+        }
+        Action::SwitchPane => {
+            if let Err(e) = app.switch_pane() {
+                app.error_message = Some(format!("Error switching pane: {}", e));
+            }
+        }
+        Action::CopyToOtherPane | Action::MoveToOtherPane => {
+            if let Some(dest) = app.other_pane.as_ref().map(|p| p.current_dir.clone()) {
+                let sources = if !app.selected_paths.is_empty() {
+                    app.selected_paths.clone()
+                } else {
+                    app.get_selected_path().into_iter().collect()
+                };
+                if !sources.is_empty() {
+                    let started = if action == Action::CopyToOtherPane {
+                        crate::file_ops::copy_items(app, &sources, &dest)
+                    } else {
+                        crate::file_ops::move_items(app, &sources, &dest)
+                    };
+                    // Marks stay when the transfer was refused, so it can be retried
+                    if started {
+                        app.selected_paths.clear();
+                    }
+                }
+            }
+        }
+
+        // Toggle preview pane
+        Action::TogglePreview => {
+            app.toggle_preview();
+        }
+
+        // Toggle miller columns layout
+        Action::ToggleMillerColumns => {
+            app.toggle_miller_columns();
+        }
+
         // Mark toggle
-        (KeyCode::Char('m'), KeyModifiers::NONE) => {
-            if let Some(path) = app.get_selected_path() {
-                // Toggle mark on current file
+        Action::ToggleMark => {
+            // Toggle marks on the current file and the ones below it (with a count)
+            for path in app.paths_from_cursor(repeat) {
                 if let Some(pos) = app.selected_paths.iter().position(|p| p == &path) {
                     // Already marked, unmark it
                     app.selected_paths.remove(pos);
@@ -184,10 +321,14 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
                     app.selected_paths.push(path);
                 }
             }
+            // Leave the cursor on the last toggled file
+            for _ in 1..repeat {
+                app.next();
+            }
         }
 
         // Visual Multi mode (Shift+V)
-        (KeyCode::Char('V'), KeyModifiers::SHIFT) => {
+        Action::VisualMode => {
             app.mode = Mode::VisualMulti;
             if let Some(path) = app.get_selected_path() {
                 // Start with current file selected
@@ -198,29 +339,24 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
         }
 
         // Copy (yy or y on marked files)
-        (KeyCode::Char('y'), KeyModifiers::NONE) => {
-            if app.last_key == "y" {
-                // yy: copy current file if no marks
-                if app.selected_paths.is_empty() {
-                    if let Some(path) = app.get_selected_path() {
-                        app.flash_copied_paths = vec![path.clone()];
-                        app.clipboard = ClipboardOperation::Copy(vec![path]);
-                    }
-                }
-                app.last_key.clear();
-            } else if !app.selected_paths.is_empty() {
-                // y: copy all marked files
-                let paths = app.selected_paths.clone();
-                if !paths.is_empty() {
-                    app.flash_copied_paths = paths.clone();
-                    app.clipboard = ClipboardOperation::Copy(paths);
-                    app.selected_paths.clear();
-                }
+        Action::YankCurrent if app.selected_paths.is_empty() => {
+            // yy: copy current file (and the ones below it with a count) if no marks
+            let paths = app.paths_from_cursor(repeat);
+            if !paths.is_empty() {
+                app.flash_copied_paths = paths.clone();
+                app.clipboard = ClipboardOperation::Copy(paths);
             }
         }
+        Action::Yank if !app.selected_paths.is_empty() => {
+            // y: copy all marked files
+            let paths = app.selected_paths.clone();
+            app.flash_copied_paths = paths.clone();
+            app.clipboard = ClipboardOperation::Copy(paths);
+            app.selected_paths.clear();
+        }
 
         // Cut (x on current or marked files)
-        (KeyCode::Char('x'), KeyModifiers::NONE) => {
+        Action::Cut => {
             if !app.selected_paths.is_empty() {
                 // Cut all marked files
                 let paths = app.selected_paths.clone();
@@ -228,44 +364,106 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
                     app.clipboard = ClipboardOperation::Cut(paths);
                     app.selected_paths.clear();
                 }
-            } else if let Some(path) = app.get_selected_path() {
-                // Cut current file
-                app.clipboard = ClipboardOperation::Cut(vec![path]);
+            } else {
+                // Cut current file (and the ones below it with a count)
+                let paths = app.paths_from_cursor(repeat);
+                if !paths.is_empty() {
+                    app.clipboard = ClipboardOperation::Cut(paths);
+                }
             }
         }
 
-        // Paste
-        (KeyCode::Char('p'), KeyModifiers::NONE) => {
+        // Paste (runs in the background, the listing refreshes when the job finishes)
+        Action::Paste => {
             crate::file_ops::paste(app)?;
-            app.load_directory()?;
-            app.start_dir_size_calculation();
+        }
+
+        // Jobs panel
+        Action::ShowJobs => {
+            let selected = if app.jobs.is_empty() { None } else { Some(0) };
+            app.jobs_state.select(selected);
+            app.mode = Mode::Jobs;
+        }
+
+        // Undo / redo file operations
+        Action::Undo => {
+            let result = app.journal.undo();
+            report_journal_result(app, result, true)?;
+        }
+        Action::Redo => {
+            let result = app.journal.redo(&app.config.behavior.delete_mode);
+            report_journal_result(app, result, false)?;
         }
 
         // Search
-        (KeyCode::Char('/'), KeyModifiers::NONE) => {
+        Action::Search => {
             app.mode = Mode::Search;
-            app.search_query.clear();
+            app.tab.search_query.clear();
+        }
+
+        // Filter the listing (keeps the current filter for editing)
+        Action::Filter => {
+            app.mode = Mode::Filter;
+        }
+
+        // Recursive fuzzy finder
+        Action::Find => {
+            app.open_finder();
+        }
+
+        // Search file contents
+        Action::Grep => {
+            app.mode = Mode::GrepInput;
+        }
+
+        // Step through search matches
+        Action::SearchNext => {
+            app.jump_to_match(true);
+        }
+        Action::SearchPrevious => {
+            app.jump_to_match(false);
+        }
+
+        // Search for the name under the cursor
+        Action::SearchCurrentName => {
+            app.search_current_name();
         }
 
         // Sort menu
-        (KeyCode::Char('s'), KeyModifiers::NONE) => {
+        Action::SortMenu => {
             app.mode = Mode::SortMenu;
         }
 
         // Toggle sort order
-        (KeyCode::Char('o'), KeyModifiers::NONE) => {
+        Action::ToggleSortOrder => {
             app.sort_ascending = !app.sort_ascending;
             app.sort_files();
         }
 
+        // Edit the current or marked files in $VISUAL / $EDITOR
+        Action::Edit => {
+            let paths = if app.selected_paths.is_empty() {
+                app.get_selected_path().into_iter().collect()
+            } else {
+                app.selected_paths.clone()
+            };
+            if !paths.is_empty() {
+                app.external = Some(External::Edit(paths));
+            }
+        }
+
+        Action::OpenWith => {
+            app.open_with_menu();
+        }
+
         // Create file/folder
-        (KeyCode::Char('a'), KeyModifiers::NONE) => {
+        Action::Create => {
             app.mode = Mode::Create;
             app.create_input.clear();
         }
 
         // Rename (r = without extension, R = with extension)
-        (KeyCode::Char('r'), KeyModifiers::NONE) => {
+        Action::Rename => {
             if let Some(path) = app.get_selected_path() {
                 let filename = path.file_name()
                     .and_then(|n| n.to_str())
@@ -290,7 +488,34 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
             }
         }
 
-        (KeyCode::Char('R'), KeyModifiers::SHIFT) => {
+        // Rename several entries at once in $EDITOR
+        Action::BulkRename => {
+            app.start_bulk_rename();
+        }
+
+        // Rename them with a regex or template instead
+        Action::PatternRename => {
+            app.start_pattern_rename();
+        }
+
+        // Pack the marked files (or the current one) into a new archive
+        Action::Compress => {
+            let paths = if app.selected_paths.is_empty() {
+                app.paths_from_cursor(repeat)
+            } else {
+                app.selected_paths.clone()
+            };
+            app.start_compress(paths);
+        }
+
+        // Extract the archive under the cursor next to it (runs in the background)
+        Action::Extract => {
+            if let Some(path) = app.get_selected_path() {
+                crate::file_ops::extract_archive(app, &path);
+            }
+        }
+
+        Action::RenameFull => {
             if let Some(path) = app.get_selected_path() {
                 let filename = path.file_name()
                     .and_then(|n| n.to_str())
@@ -306,19 +531,17 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
         }
 
         // Delete
-        (KeyCode::Char('d'), KeyModifiers::NONE) => {
+        Action::Delete => {
             // Store current position before deletion
-            let current_index = app.list_state.selected().unwrap_or(0);
+            let current_index = app.tab.list_state.selected().unwrap_or(0);
 
             // Check if there are marked files (selected_paths)
             let paths_to_delete: Vec<_> = if !app.selected_paths.is_empty() {
                 // Delete all marked files
                 app.selected_paths.clone()
-            } else if let Some(path) = app.get_selected_path() {
-                // Delete current file
-                vec![path]
             } else {
-                vec![]
+                // Delete current file (and the ones below it with a count)
+                app.paths_from_cursor(repeat)
             };
 
             if !paths_to_delete.is_empty() {
@@ -326,36 +549,40 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
                     app.delete_targets = paths_to_delete;
                     app.mode = Mode::DeleteConfirm;
                 } else {
-                    for path in &paths_to_delete {
-                        crate::file_ops::delete_path(path)?;
-                    }
-                    app.selected_paths.clear();
-                    app.load_directory()?;
-
-                    // Restore cursor position intelligently
-                    let new_count = app.filtered_indices.len();
-                    if new_count > 0 {
-                        // Try to stay at same index, or move up if we're past the end
-                        let new_index = if current_index >= new_count {
-                            new_count - 1
-                        } else {
-                            current_index
-                        };
-                        app.list_state.select(Some(new_index));
-                    }
-
-                    app.start_dir_size_calculation();
+                    delete_paths(app, &paths_to_delete, current_index)?;
                 }
             }
         }
 
+        // Trash browser
+        Action::ShowTrash => {
+            if let Err(e) = app.open_trash() {
+                app.error_message = Some(format!("Error reading trash: {}", e));
+            }
+        }
+
+        Action::FrecencyJump => {
+            app.open_jump();
+        }
+
+        // Bookmarks (the next key names the bookmark)
+        Action::SetBookmark => {
+            app.mode = Mode::BookmarkSet;
+        }
+        Action::JumpToBookmark => {
+            app.mode = Mode::BookmarkJump;
+        }
+        Action::ShowBookmarks => {
+            app.open_bookmarks();
+        }
+
         // Help
-        (KeyCode::Char('?'), _) => {
+        Action::Help => {
             app.mode = Mode::Help;
         }
 
         // Esc - clear marks, cut clipboard and search highlights
-        (KeyCode::Esc, KeyModifiers::NONE) => {
+        Action::Cancel => {
             // Clear marked files
             if !app.selected_paths.is_empty() {
                 app.selected_paths.clear();
@@ -365,9 +592,13 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
                 app.clipboard = ClipboardOperation::None;
             }
             // Clear search highlights if any
-            else if !app.search_highlights.is_empty() {
+            else if !app.tab.search_highlights.is_empty() {
                 app.clear_search();
             }
+            // Clear the filter
+            else if !app.tab.filter_query.is_empty() {
+                app.clear_filter();
+            }
         }
 
         _ => {}
@@ -376,6 +607,33 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Resu
     Ok(())
 }
 
+fn handle_filter_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+            app.clear_filter();
+        }
+        KeyCode::Enter => {
+            app.mode = Mode::Normal;
+            // Keep filtering while navigating this directory
+        }
+        KeyCode::Down => app.next(),
+        KeyCode::Up => app.previous(),
+        KeyCode::Backspace => {
+            let mut query = app.tab.filter_query.clone();
+            query.pop();
+            app.set_filter(query);
+        }
+        KeyCode::Char(c) => {
+            let query = format!("{}{}", app.tab.filter_query, c);
+            app.set_filter(query);
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
 fn handle_search_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
         KeyCode::Esc => {
@@ -387,11 +645,11 @@ fn handle_search_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             // Keep highlights active
         }
         KeyCode::Backspace => {
-            app.search_query.pop();
+            app.tab.search_query.pop();
             crate::fuzzy::update_search(app);
         }
         KeyCode::Char(c) => {
-            app.search_query.push(c);
+            app.tab.search_query.push(c);
             crate::fuzzy::update_search(app);
         }
         _ => {}
@@ -426,86 +684,88 @@ fn handle_sort_menu(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
-fn handle_visual_multi_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -> Result<()> {
-    // Handle gg (jump to top and select all from current to top)
-    if two_key_combo == "gg" {
-        let current_index = app.list_state.selected().unwrap_or(0);
-        // Select all files from 0 to current position
-        for i in 0..=current_index {
-            if let Some(&file_idx) = app.filtered_indices.get(i) {
-                let path = app.files[file_idx].path.clone();
-                if !app.selected_paths.contains(&path) {
-                    app.selected_paths.push(path);
+fn handle_visual_action(app: &mut App, action: Action, count: Option<usize>) -> Result<()> {
+    let repeat = count.unwrap_or(1);
+
+    match action {
+        // Jump to top and select all from current to top
+        Action::GoTop => {
+            let current_index = app.tab.list_state.selected().unwrap_or(0);
+            for i in 0..=current_index {
+                if let Some(&file_idx) = app.tab.filtered_indices.get(i) {
+                    let path = app.tab.files[file_idx].path.clone();
+                    if !app.selected_paths.contains(&path) {
+                        app.selected_paths.push(path);
+                    }
                 }
             }
+            app.tab.list_state.select(Some(0));
         }
-        app.list_state.select(Some(0));
-        app.last_key.clear();
-        return Ok(());
-    }
-
-    match (key.code, key.modifiers) {
         // Jump to bottom and select all
-        (KeyCode::Char('G'), KeyModifiers::SHIFT) => {
-            let current_index = app.list_state.selected().unwrap_or(0);
-            let count = app.filtered_indices.len();
-            if count > 0 {
+        Action::GoBottom => {
+            let current_index = app.tab.list_state.selected().unwrap_or(0);
+            let total = app.tab.filtered_indices.len();
+            if total > 0 {
                 // Select all files from current position to bottom
-                for i in current_index..count {
-                    if let Some(&file_idx) = app.filtered_indices.get(i) {
-                        let path = app.files[file_idx].path.clone();
+                for i in current_index..total {
+                    if let Some(&file_idx) = app.tab.filtered_indices.get(i) {
+                        let path = app.tab.files[file_idx].path.clone();
                         if !app.selected_paths.contains(&path) {
                             app.selected_paths.push(path);
                         }
                     }
                 }
-                app.list_state.select(Some(count - 1));
+                app.tab.list_state.select(Some(total - 1));
             }
         }
-        (KeyCode::Enter, _) => {
+        Action::Confirm => {
             // Exit mode and keep marks
             app.mode = Mode::Normal;
         }
-        (KeyCode::Esc, _) => {
+        Action::Cancel => {
             // Exit mode and clear all marks
             app.mode = Mode::Normal;
             app.selected_paths.clear();
         }
-        (KeyCode::Char('j'), _) | (KeyCode::Down, _) => {
-            // Store current position before moving
-            let prev_path = app.get_selected_path();
-            app.next();
-            if let Some(path) = app.get_selected_path() {
-                // Add to selection if not already selected
-                if !app.selected_paths.contains(&path) {
-                    app.selected_paths.push(path);
+        Action::MoveDown => {
+            for _ in 0..repeat {
+                // Store current position before moving
+                let prev_path = app.get_selected_path();
+                app.next();
+                if let Some(path) = app.get_selected_path() {
+                    // Add to selection if not already selected
+                    if !app.selected_paths.contains(&path) {
+                        app.selected_paths.push(path);
+                    }
                 }
-            }
-            // When moving down, ensure the previous position stays selected
-            if let Some(prev) = prev_path {
-                if !app.selected_paths.contains(&prev) {
-                    app.selected_paths.push(prev);
+                // When moving down, ensure the previous position stays selected
+                if let Some(prev) = prev_path {
+                    if !app.selected_paths.contains(&prev) {
+                        app.selected_paths.push(prev);
+                    }
                 }
             }
         }
-        (KeyCode::Char('k'), _) | (KeyCode::Up, _) => {
-            // Store current position before moving
-            let prev_path = app.get_selected_path();
-            app.previous();
-            // When moving up, deselect the previous position
-            if let Some(prev) = prev_path {
-                if let Some(pos) = app.selected_paths.iter().position(|p| p == &prev) {
-                    app.selected_paths.remove(pos);
+        Action::MoveUp => {
+            for _ in 0..repeat {
+                // Store current position before moving
+                let prev_path = app.get_selected_path();
+                app.previous();
+                // When moving up, deselect the previous position
+                if let Some(prev) = prev_path {
+                    if let Some(pos) = app.selected_paths.iter().position(|p| p == &prev) {
+                        app.selected_paths.remove(pos);
+                    }
                 }
-            }
-            // Make sure current position is selected
-            if let Some(path) = app.get_selected_path() {
-                if !app.selected_paths.contains(&path) {
-                    app.selected_paths.push(path);
+                // Make sure current position is selected
+                if let Some(path) = app.get_selected_path() {
+                    if !app.selected_paths.contains(&path) {
+                        app.selected_paths.push(path);
+                    }
                 }
             }
         }
-        (KeyCode::Char('m'), _) => {
+        Action::ToggleMark => {
             // Deselect current file
             if let Some(path) = app.get_selected_path() {
                 if let Some(pos) = app.selected_paths.iter().position(|p| p == &path) {
@@ -513,7 +773,7 @@ fn handle_visual_multi_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -
                 }
             }
         }
-        (KeyCode::Char('y'), _) => {
+        Action::Yank => {
             // Copy all selected
             let paths = app.selected_paths.clone();
             if !paths.is_empty() {
@@ -523,7 +783,7 @@ fn handle_visual_multi_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -
             app.mode = Mode::Normal;
             app.selected_paths.clear();
         }
-        (KeyCode::Char('x'), _) => {
+        Action::Cut => {
             // Cut all selected
             let paths = app.selected_paths.clone();
             if !paths.is_empty() {
@@ -532,7 +792,13 @@ fn handle_visual_multi_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -
             app.mode = Mode::Normal;
             app.selected_paths.clear();
         }
-        (KeyCode::Char('d'), _) => {
+        Action::Compress => {
+            // Compress all selected
+            let paths = app.selected_paths.clone();
+            app.mode = Mode::Normal;
+            app.start_compress(paths);
+        }
+        Action::Delete => {
             // Delete all selected
             let paths_to_delete = app.selected_paths.clone();
             if !paths_to_delete.is_empty() {
@@ -541,27 +807,8 @@ fn handle_visual_multi_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -
                     app.mode = Mode::DeleteConfirm;
                 } else {
                     // Store current position before deletion
-                    let current_index = app.list_state.selected().unwrap_or(0);
-
-                    for path in &paths_to_delete {
-                        crate::file_ops::delete_path(path)?;
-                    }
-                    app.selected_paths.clear();
-                    app.load_directory()?;
-
-                    // Restore cursor position intelligently
-                    let new_count = app.filtered_indices.len();
-                    if new_count > 0 {
-                        // Try to stay at same index, or move up if we're past the end
-                        let new_index = if current_index >= new_count {
-                            new_count - 1
-                        } else {
-                            current_index
-                        };
-                        app.list_state.select(Some(new_index));
-                    }
-
-                    app.start_dir_size_calculation();
+                    let current_index = app.tab.list_state.selected().unwrap_or(0);
+                    delete_paths(app, &paths_to_delete, current_index)?;
                     app.mode = Mode::Normal;
                 }
             }
@@ -572,6 +819,28 @@ fn handle_visual_multi_mode(app: &mut App, key: KeyEvent, two_key_combo: &str) -
     Ok(())
 }
 
+fn handle_compress_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+            app.compress_input.clear();
+            app.compress_sources.clear();
+        }
+        KeyCode::Enter => {
+            app.finish_compress();
+        }
+        KeyCode::Backspace => {
+            app.compress_input.pop();
+        }
+        KeyCode::Char(c) => {
+            app.compress_input.push(c);
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
 fn handle_create_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
         KeyCode::Esc => {
@@ -580,18 +849,25 @@ fn handle_create_mode(app: &mut App, key: KeyEvent) -> Result<()> {
         }
         KeyCode::Enter => {
             if !app.create_input.is_empty() {
-                let path = app.current_dir.join(&app.create_input);
+                let path = app.tab.current_dir.join(&app.create_input);
+                let is_dir = app.create_input.ends_with('/');
 
-                if app.create_input.ends_with('/') {
-                    // Create directory
-                    crate::file_ops::create_directory(&path)?;
+                if path.exists() {
+                    app.error_message = Some(format!("{} already exists", app.create_input));
                 } else {
-                    // Create file
-                    crate::file_ops::create_file(&path)?;
-                }
+                    let created = if is_dir {
+                        // Create directory
+                        crate::file_ops::create_directory(&path)?
+                    } else {
+                        // Create file
+                        crate::file_ops::create_file(&path)?;
+                        vec![path.clone()]
+                    };
+                    app.journal.record(Operation::Create { path, is_dir, created });
 
-                app.load_directory()?;
-                app.start_dir_size_calculation();
+                    app.load_directory()?;
+                    app.start_dir_size_calculation();
+                }
             }
             app.mode = Mode::Normal;
             app.create_input.clear();
@@ -618,19 +894,25 @@ fn handle_rename_mode(app: &mut App, key: KeyEvent) -> Result<()> {
         KeyCode::Enter => {
             if !app.rename_input.is_empty() {
                 if let Some(old_path) = &app.rename_target {
-                    let new_path = app.current_dir.join(&app.rename_input);
+                    let new_path = app.tab.current_dir.join(&app.rename_input);
 
                     // Don't rename if the name hasn't changed
                     if old_path != &new_path {
-                        if let Err(e) = std::fs::rename(old_path, &new_path) {
+                        if new_path.exists() {
+                            app.error_message = Some(format!("{} already exists", app.rename_input));
+                        } else if let Err(e) = std::fs::rename(old_path, &new_path) {
                             app.error_message = Some(format!("Failed to rename: {}", e));
                         } else {
+                            app.journal.record(Operation::Rename {
+                                from: old_path.clone(),
+                                to: new_path.clone(),
+                            });
                             app.load_directory()?;
                             app.start_dir_size_calculation();
 
                             // Select the renamed file
-                            if let Some(idx) = app.files.iter().position(|f| f.path == new_path) {
-                                app.list_state.select(Some(idx));
+                            if let Some(idx) = app.tab.filtered_indices.iter().position(|&i| app.tab.files[i].path == new_path) {
+                                app.tab.list_state.select(Some(idx));
                             }
                         }
                     }
@@ -640,26 +922,18 @@ fn handle_rename_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             app.rename_input.clear();
             app.rename_target = None;
         }
-        KeyCode::Backspace => {
-            if app.rename_cursor_pos > 0 {
-                app.rename_input.remove(app.rename_cursor_pos - 1);
-                app.rename_cursor_pos -= 1;
-            }
+        KeyCode::Backspace if app.rename_cursor_pos > 0 => {
+            app.rename_input.remove(app.rename_cursor_pos - 1);
+            app.rename_cursor_pos -= 1;
         }
-        KeyCode::Delete => {
-            if app.rename_cursor_pos < app.rename_input.len() {
-                app.rename_input.remove(app.rename_cursor_pos);
-            }
+        KeyCode::Delete if app.rename_cursor_pos < app.rename_input.len() => {
+            app.rename_input.remove(app.rename_cursor_pos);
         }
-        KeyCode::Left => {
-            if app.rename_cursor_pos > 0 {
-                app.rename_cursor_pos -= 1;
-            }
+        KeyCode::Left if app.rename_cursor_pos > 0 => {
+            app.rename_cursor_pos -= 1;
         }
-        KeyCode::Right => {
-            if app.rename_cursor_pos < app.rename_input.len() {
-                app.rename_cursor_pos += 1;
-            }
+        KeyCode::Right if app.rename_cursor_pos < app.rename_input.len() => {
+            app.rename_cursor_pos += 1;
         }
         KeyCode::Home => {
             app.rename_cursor_pos = 0;
@@ -692,33 +966,406 @@ fn handle_delete_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
         KeyCode::Char('y') | KeyCode::Char('Y') => {
             // Store current position before deletion
-            let current_index = app.list_state.selected().unwrap_or(0);
+            let current_index = app.tab.list_state.selected().unwrap_or(0);
 
-            for path in &app.delete_targets {
-                crate::file_ops::delete_path(path)?;
-            }
+            let targets = std::mem::take(&mut app.delete_targets);
+            app.mode = Mode::Normal;
+            delete_paths(app, &targets, current_index)?;
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Enter => {
             app.delete_targets.clear();
-            app.selected_paths.clear();
-            app.load_directory()?;
-
-            // Restore cursor position intelligently
-            let new_count = app.filtered_indices.len();
-            if new_count > 0 {
-                // Try to stay at same index, or move up if we're past the end
-                let new_index = if current_index >= new_count {
-                    new_count - 1
+            app.mode = Mode::Normal;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_rename_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let count = app.rename_plan.as_ref().map(|plan| plan.renames.len()).unwrap_or(0);
+
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Enter => {
+            app.apply_rename_plan();
+        }
+        KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+            app.rename_plan = None;
+            app.mode = Mode::Normal;
+            app.status_message = Some(String::from("Bulk rename cancelled"));
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            if let Some(i) = app.rename_plan_state.selected() {
+                app.rename_plan_state.select(Some((i + 1).min(count.saturating_sub(1))));
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if let Some(i) = app.rename_plan_state.selected() {
+                app.rename_plan_state.select(Some(i.saturating_sub(1)));
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_pattern_rename_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(pattern) = app.pattern_rename.as_mut() else {
+        app.mode = Mode::Normal;
+        return Ok(());
+    };
+    let count = pattern.originals.len();
+
+    match (key.code, key.modifiers) {
+        (KeyCode::Esc, _) => {
+            app.pattern_rename = None;
+            app.error_message = None;
+            app.mode = Mode::Normal;
+        }
+        (KeyCode::Enter, _) => {
+            app.apply_pattern_rename();
+        }
+        (KeyCode::Tab, _) | (KeyCode::BackTab, _) => {
+            pattern.editing_replace = !pattern.editing_replace;
+        }
+        (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) | (KeyCode::Char('j'), KeyModifiers::CONTROL) => {
+            if let Some(i) = pattern.list_state.selected() {
+                pattern.list_state.select(Some((i + 1).min(count.saturating_sub(1))));
+            }
+        }
+        (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) | (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+            if let Some(i) = pattern.list_state.selected() {
+                pattern.list_state.select(Some(i.saturating_sub(1)));
+            }
+        }
+        (KeyCode::Backspace, _) => {
+            let field = if pattern.editing_replace { &mut pattern.replace } else { &mut pattern.find };
+            field.pop();
+            pattern.update();
+            app.error_message = None;
+        }
+        (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+            let field = if pattern.editing_replace { &mut pattern.replace } else { &mut pattern.find };
+            field.push(c);
+            pattern.update();
+            app.error_message = None;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_open_with_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let count = app.open_with_choices.len();
+    let mut chosen = None;
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.mode = Mode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            if let Some(i) = app.open_with_state.selected() {
+                app.open_with_state.select(Some((i + 1).min(count.saturating_sub(1))));
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if let Some(i) = app.open_with_state.selected() {
+                app.open_with_state.select(Some(i.saturating_sub(1)));
+            }
+        }
+        KeyCode::Char('l') | KeyCode::Enter => {
+            chosen = app.open_with_state.selected();
+        }
+        // Pick an entry by its number
+        KeyCode::Char(c @ '1'..='9') => {
+            let index = c as usize - '1' as usize;
+            if index < count {
+                chosen = Some(index);
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(index) = chosen {
+        app.mode = Mode::Normal;
+        if let Some(path) = app.open_with_path.take() {
+            let opener = app.open_with_choices.get(index).cloned().flatten();
+            app.open_path(path, opener);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_bookmark_key_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let setting = app.mode == Mode::BookmarkSet;
+    app.mode = Mode::Normal;
+
+    if let KeyCode::Char(c) = key.code {
+        if setting {
+            app.set_bookmark(c);
+        } else if let Err(e) = app.jump_to_bookmark(c) {
+            app.error_message = Some(format!("{}", e));
+        } else {
+            app.error_message = None;
+        }
+    }
+    // Any other key (e.g. Esc) cancels
+
+    Ok(())
+}
+
+fn handle_bookmarks_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let count = app.bookmarks.len();
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('B') => {
+            app.mode = Mode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            if let Some(i) = app.bookmarks_state.selected() {
+                app.bookmarks_state.select(Some((i + 1).min(count.saturating_sub(1))));
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if let Some(i) = app.bookmarks_state.selected() {
+                app.bookmarks_state.select(Some(i.saturating_sub(1)));
+            }
+        }
+        // Jump to the selected bookmark
+        KeyCode::Char('l') | KeyCode::Enter => {
+            let dir = app.bookmarks_state.selected()
+                .and_then(|i| app.bookmarks.entries().get(i).map(|(_, dir)| dir.to_path_buf()));
+            if let Some(dir) = dir {
+                app.mode = Mode::Normal;
+                if !dir.is_dir() {
+                    app.error_message = Some(format!("Bookmarked directory no longer exists: {}", dir.display()));
+                } else if let Err(e) = app.jump_to(dir, None) {
+                    app.error_message = Some(format!("Error loading directory: {}", e));
+                }
+            }
+        }
+        // Delete the selected bookmark
+        KeyCode::Char('d') | KeyCode::Char('x') => {
+            let key = app.bookmarks_state.selected()
+                .and_then(|i| app.bookmarks.entries().get(i).map(|(key, _)| key.to_string()));
+            if let Some(key) = key {
+                if let Err(e) = app.bookmarks.remove(&key) {
+                    app.error_message = Some(format!("Could not save bookmarks: {}", e));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    // Keep the cursor inside the list after removals
+    let count = app.bookmarks.len();
+    if count == 0 {
+        app.bookmarks_state.select(None);
+    } else if app.bookmarks_state.selected().is_none_or(|i| i >= count) {
+        app.bookmarks_state.select(Some(count - 1));
+    }
+
+    Ok(())
+}
+
+fn handle_trash_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let count = app.trash_items.len();
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => {
+            app.trash_items.clear();
+            app.mode = Mode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            if let Some(i) = app.trash_state.selected() {
+                app.trash_state.select(Some((i + 1).min(count.saturating_sub(1))));
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if let Some(i) = app.trash_state.selected() {
+                app.trash_state.select(Some(i.saturating_sub(1)));
+            }
+        }
+        // Restore to original location
+        KeyCode::Char('r') | KeyCode::Enter => {
+            if let Some(i) = app.trash_state.selected() {
+                let item = app.trash_items[i].clone();
+                if let Err(e) = crate::trash::restore(&item) {
+                    app.error_message = Some(format!("Failed to restore: {}", e));
                 } else {
-                    current_index
-                };
-                app.list_state.select(Some(new_index));
+                    app.error_message = None;
+                    app.trash_items.remove(i);
+                    app.journal.record(Operation::Restore(vec![item]));
+                    app.load_directory()?;
+                    app.start_dir_size_calculation();
+                }
             }
+        }
+        // Permanently remove from trash, after confirmation
+        KeyCode::Char('D') if app.trash_state.selected().is_some() => {
+            app.mode = Mode::PurgeConfirm;
+        }
+        _ => {}
+    }
 
-            app.start_dir_size_calculation();
+    clamp_trash_selection(app);
+    Ok(())
+}
+
+fn handle_purge_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    app.mode = Mode::Trash;
+    if let (KeyCode::Char('y') | KeyCode::Char('Y'), Some(i)) = (key.code, app.trash_state.selected()) {
+        let item = app.trash_items[i].clone();
+        if let Err(e) = crate::trash::purge(&item) {
+            app.error_message = Some(format!("Failed to purge: {}", e));
+        } else {
+            app.trash_items.remove(i);
+        }
+    }
+
+    clamp_trash_selection(app);
+    Ok(())
+}
+
+// Keeps the cursor inside the trash list after removals
+fn clamp_trash_selection(app: &mut App) {
+    let count = app.trash_items.len();
+    if count == 0 {
+        app.trash_state.select(None);
+    } else if app.trash_state.selected().is_none_or(|i| i >= count) {
+        app.trash_state.select(Some(count - 1));
+    }
+}
+
+fn handle_paste_conflict_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    // Lowercase answers the current conflict, uppercase applies to all remaining ones
+    let (resolution, apply_to_all) = match key.code {
+        KeyCode::Char('o') => (ConflictResolution::Overwrite, false),
+        KeyCode::Char('O') => (ConflictResolution::Overwrite, true),
+        KeyCode::Char('s') => (ConflictResolution::Skip, false),
+        KeyCode::Char('S') => (ConflictResolution::Skip, true),
+        KeyCode::Char('r') => (ConflictResolution::Rename, false),
+        KeyCode::Char('R') => (ConflictResolution::Rename, true),
+        KeyCode::Char('n') => (ConflictResolution::OverwriteIfNewer, false),
+        KeyCode::Char('N') => (ConflictResolution::OverwriteIfNewer, true),
+        KeyCode::Esc => {
+            crate::file_ops::cancel_transfer(app);
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+
+    crate::file_ops::resolve_conflict(app, resolution, apply_to_all);
+    Ok(())
+}
+
+fn handle_jobs_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let count = app.jobs.jobs.len();
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('J') => {
             app.mode = Mode::Normal;
         }
-        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Enter => {
-            app.delete_targets.clear();
+        KeyCode::Char('j') | KeyCode::Down => {
+            if let Some(i) = app.jobs_state.selected() {
+                app.jobs_state.select(Some((i + 1).min(count.saturating_sub(1))));
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if let Some(i) = app.jobs_state.selected() {
+                app.jobs_state.select(Some(i.saturating_sub(1)));
+            }
+        }
+        // Cancel the selected job
+        KeyCode::Char('x') | KeyCode::Char('d') => {
+            if let Some(job) = app.jobs_state.selected().and_then(|i| app.jobs.jobs.get(i)) {
+                let id = job.id;
+                app.jobs.cancel(id);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_finder_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(finder) = app.finder.as_mut() else {
+        app.mode = Mode::Normal;
+        return Ok(());
+    };
+
+    match (key.code, key.modifiers) {
+        (KeyCode::Esc, _) => {
+            app.close_finder();
+        }
+        (KeyCode::Enter, _) => {
+            if let Some(path) = finder.selected_path() {
+                app.close_finder();
+                // Open the directory containing the match with the cursor on it
+                let dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_else(|| path.clone());
+                if let Err(e) = app.jump_to(dir, Some(&path)) {
+                    app.error_message = Some(format!("Error loading directory: {}", e));
+                }
+            }
+        }
+        (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) | (KeyCode::Char('j'), KeyModifiers::CONTROL) => {
+            finder.next();
+        }
+        (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) | (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+            finder.previous();
+        }
+        (KeyCode::Backspace, _) => {
+            let mut query = finder.query.clone();
+            query.pop();
+            finder.set_query(query);
+        }
+        (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+            let query = format!("{}{}", finder.query, c);
+            finder.set_query(query);
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_jump_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    match (key.code, key.modifiers) {
+        (KeyCode::Esc, _) => {
+            app.mode = Mode::Normal;
+        }
+        (KeyCode::Enter, _) => {
+            let dir = app.jump_state.selected()
+                .and_then(|i| app.jump_matches.get(i))
+                .map(|found| found.path.clone());
             app.mode = Mode::Normal;
+            if let Some(dir) = dir {
+                if let Err(e) = app.jump_to(dir, None) {
+                    app.error_message = Some(format!("Error loading directory: {}", e));
+                }
+            }
+        }
+        (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) | (KeyCode::Char('j'), KeyModifiers::CONTROL) => {
+            if let Some(i) = app.jump_state.selected() {
+                app.jump_state.select(Some((i + 1).min(app.jump_matches.len().saturating_sub(1))));
+            }
+        }
+        (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) | (KeyCode::Char('k'), KeyModifiers::CONTROL) => {
+            if let Some(i) = app.jump_state.selected() {
+                app.jump_state.select(Some(i.saturating_sub(1)));
+            }
+        }
+        (KeyCode::Backspace, _) => {
+            app.jump_input.pop();
+            app.update_jump_matches();
+        }
+        (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+            app.jump_input.push(c);
+            app.update_jump_matches();
         }
         _ => {}
     }
@@ -726,12 +1373,130 @@ fn handle_delete_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
-fn format_key(key: &KeyEvent) -> String {
+fn handle_grep_input_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
-        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::SHIFT) => {
-            c.to_uppercase().to_string()
+        KeyCode::Esc => {
+            app.mode = Mode::Normal;
+        }
+        KeyCode::Enter => {
+            if app.grep_input.is_empty() {
+                app.mode = Mode::Normal;
+            } else {
+                app.start_grep();
+            }
+        }
+        KeyCode::Backspace => {
+            app.grep_input.pop();
+        }
+        KeyCode::Char(c) => {
+            app.grep_input.push(c);
         }
-        KeyCode::Char(c) => c.to_string(),
-        _ => String::new(),
+        _ => {}
     }
+
+    Ok(())
+}
+
+fn handle_grep_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(grep) = app.grep.as_mut() else {
+        app.mode = Mode::Normal;
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.close_grep();
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            grep.next();
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            grep.previous();
+        }
+        KeyCode::Char('g') if !grep.matches.is_empty() => {
+            grep.list_state.select(Some(0));
+        }
+        KeyCode::Char('G') if !grep.matches.is_empty() => {
+            grep.list_state.select(Some(grep.matches.len() - 1));
+        }
+        // Refine the search
+        KeyCode::Char('S') | KeyCode::Char('/') => {
+            app.close_grep();
+            app.mode = Mode::GrepInput;
+        }
+        KeyCode::Enter | KeyCode::Char('l') | KeyCode::Right => {
+            if let Some(path) = grep.selected_path() {
+                app.close_grep();
+                // Open the file's directory with the cursor on it
+                let dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_else(|| path.clone());
+                if let Err(e) = app.jump_to(dir, Some(&path)) {
+                    app.error_message = Some(format!("Error loading directory: {}", e));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn report_journal_result(app: &mut App, result: Result<Option<String>>, is_undo: bool) -> Result<()> {
+    let (action, done) = if is_undo { ("undo", "Undid") } else { ("redo", "Redid") };
+    match result {
+        Ok(Some(description)) => {
+            app.error_message = None;
+            app.status_message = Some(format!("{} {}", done, description));
+        }
+        Ok(None) => {
+            app.status_message = Some(format!("Nothing to {}", action));
+        }
+        Err(e) => {
+            app.error_message = Some(format!("Failed to {}: {}", action, e));
+        }
+    }
+
+    // The operation may have touched anything, so refresh and keep the cursor in range
+    let current_index = app.tab.list_state.selected().unwrap_or(0);
+    app.load_directory()?;
+    let count = app.tab.filtered_indices.len();
+    if count > 0 {
+        app.tab.list_state.select(Some(current_index.min(count - 1)));
+    }
+    app.start_dir_size_calculation();
+    Ok(())
+}
+
+fn delete_paths(app: &mut App, paths: &[PathBuf], current_index: usize) -> Result<()> {
+    let mut trashed = Vec::new();
+    for path in paths {
+        match crate::file_ops::delete_path(path, &app.config.behavior.delete_mode) {
+            Ok(Some(item)) => trashed.push(item),
+            Ok(None) => {}
+            Err(e) => {
+                app.error_message = Some(format!("Failed to delete {}: {}", path.display(), e));
+            }
+        }
+    }
+    // Only trashed items can be brought back
+    if !trashed.is_empty() {
+        app.journal.record(Operation::Delete(trashed));
+    }
+    // Keep marks only on items that survived (e.g. failed deletions)
+    app.selected_paths.retain(|p| p.exists());
+    app.load_directory()?;
+
+    // Restore cursor position intelligently
+    let new_count = app.tab.filtered_indices.len();
+    if new_count > 0 {
+        // Try to stay at same index, or move up if we're past the end
+        let new_index = if current_index >= new_count {
+            new_count - 1
+        } else {
+            current_index
+        };
+        app.tab.list_state.select(Some(new_index));
+    }
+
+    app.start_dir_size_calculation();
+    Ok(())
 }
@@ -1,24 +1,413 @@
-use crate::app::{App, ClipboardOperation};
+use crate::app::{App, ClipboardOperation, Mode};
+use crate::config::{DeleteMode, PasteConflict};
+use crate::jobs::{JobKind, JobQueue, Transfer};
+use crate::rename::RenamePlan;
+use crate::trash::TrashItem;
 use anyhow::Result;
 use fs_extra::dir;
 use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
 pub fn open_file(path: &Path) -> Result<()> {
     open::that(path)?;
     Ok(())
 }
 
+/// Runs `$VISUAL` or `$EDITOR` (falling back to vi) on `paths` and waits for it to exit.
+/// The TUI has to be suspended first, since the editor takes over the terminal.
+pub fn edit_files(paths: &[PathBuf]) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+
+    // The variable may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let mut command = Command::new(program);
+    command.args(parts).args(paths);
+    // Keep the editor on the terminal when our stdout is captured
+    if !io::stdout().is_terminal() {
+        command.stdout(io::stderr());
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| anyhow::anyhow!("Could not run {}: {}", program, e))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Creates a new file only the user can read, under an unpredictable name in the temp dir.
+/// It is never an existing file or a symlink someone else planted there.
+pub fn create_temp_file(prefix: &str, suffix: &str) -> Result<(PathBuf, fs::File)> {
+    use std::hash::{BuildHasher, RandomState};
+    use std::os::unix::fs::OpenOptionsExt;
+
+    loop {
+        let random = RandomState::new().hash_one(std::time::SystemTime::now());
+        let path = std::env::temp_dir().join(format!("{}-{}-{:016x}{}", prefix, std::process::id(), random, suffix));
+        match fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Replaces `path` with `contents` in one step, so a crash or a concurrent reader never
+/// sees a partly written file
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    fs::write(&temp, contents)?;
+    if let Err(e) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+// Maximum number of operations kept for undo
+const JOURNAL_LIMIT: usize = 100;
+
+/// A filesystem mutation recorded with enough information to reverse it
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Copy(Vec<(PathBuf, PathBuf)>), // (source, created copy)
+    Move(Vec<(PathBuf, PathBuf)>), // (original location, new location)
+    Rename { from: PathBuf, to: PathBuf },
+    BulkRename(RenamePlan),
+    Create { path: PathBuf, is_dir: bool, created: Vec<PathBuf> }, // created: every new path, outermost first
+    Compress { sources: Vec<PathBuf>, archive: PathBuf },
+    Extract(Vec<(PathBuf, PathBuf)>), // (archive, created directory)
+    Delete(Vec<TrashItem>),
+    Restore(Vec<TrashItem>), // restored from the trash browser
+    Batch(Vec<Operation>), // applied in order, reverted in reverse order
+}
+
+impl Operation {
+    pub fn describe(&self) -> String {
+        let items = |count: usize| {
+            if count == 1 {
+                String::from("1 item")
+            } else {
+                format!("{} items", count)
+            }
+        };
+        match self {
+            Operation::Copy(pairs) => format!("copy of {}", items(pairs.len())),
+            Operation::Move(pairs) => format!("move of {}", items(pairs.len())),
+            Operation::Rename { from, to } => format!(
+                "rename {} -> {}",
+                file_name_of(from),
+                file_name_of(to)
+            ),
+            Operation::BulkRename(plan) => format!("rename of {}", items(plan.renames.len())),
+            Operation::Create { path, .. } => format!("create {}", file_name_of(path)),
+            Operation::Compress { archive, .. } => format!("compress into {}", file_name_of(archive)),
+            Operation::Extract(pairs) => format!("extract of {}", items(pairs.len())),
+            Operation::Delete(trashed) => format!("delete of {}", items(trashed.len())),
+            Operation::Restore(restored) => format!("restore of {}", items(restored.len())),
+            Operation::Batch(operations) => operations
+                .iter()
+                .map(|op| op.describe())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    // Splits the operation into the smallest parts that are undone and redone on their own,
+    // in the order they were applied
+    fn into_steps(self) -> Vec<Operation> {
+        match self {
+            Operation::Copy(pairs) => pairs.into_iter().map(|pair| Operation::Copy(vec![pair])).collect(),
+            Operation::Move(pairs) => pairs.into_iter().map(|pair| Operation::Move(vec![pair])).collect(),
+            Operation::Extract(pairs) => pairs.into_iter().map(|pair| Operation::Extract(vec![pair])).collect(),
+            Operation::Delete(trashed) => trashed.into_iter().map(|item| Operation::Delete(vec![item])).collect(),
+            Operation::Restore(restored) => restored.into_iter().map(|item| Operation::Restore(vec![item])).collect(),
+            Operation::Batch(operations) => operations.into_iter().flat_map(Operation::into_steps).collect(),
+            operation => vec![operation],
+        }
+    }
+
+    // Joins steps back into one operation, merging neighbours of the same kind; None when empty
+    fn from_steps(steps: Vec<Operation>) -> Option<Operation> {
+        let mut merged: Vec<Operation> = Vec::new();
+        for step in steps {
+            match (merged.last_mut(), step) {
+                (Some(Operation::Copy(pairs)), Operation::Copy(more)) => pairs.extend(more),
+                (Some(Operation::Move(pairs)), Operation::Move(more)) => pairs.extend(more),
+                (Some(Operation::Extract(pairs)), Operation::Extract(more)) => pairs.extend(more),
+                (Some(Operation::Delete(trashed)), Operation::Delete(more)) => trashed.extend(more),
+                (Some(Operation::Restore(restored)), Operation::Restore(more)) => restored.extend(more),
+                (_, step) => merged.push(step),
+            }
+        }
+        match merged.len() {
+            0 => None,
+            1 => merged.pop(),
+            _ => Some(Operation::Batch(merged)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Journal {
+    undo_stack: Vec<Operation>,
+    redo_stack: Vec<Operation>,
+}
+
+impl Journal {
+    pub fn record(&mut self, operation: Operation) {
+        self.undo_stack.push(operation);
+        if self.undo_stack.len() > JOURNAL_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        // A new action invalidates everything that was undone before it
+        self.redo_stack.clear();
+    }
+
+    /// Reverses the most recent operation, returning a description of it. When that fails
+    /// partway, what was reverted can be redone and the rest stays on the undo stack.
+    pub fn undo(&mut self) -> Result<Option<String>> {
+        let Some(operation) = self.undo_stack.pop() else {
+            return Ok(None);
+        };
+        let description = operation.describe();
+
+        // Steps are reverted last to first
+        let mut steps = operation.into_steps();
+        let mut reverted = Vec::new();
+        while let Some(step) = steps.pop() {
+            match revert(step.clone()) {
+                Ok(step) => reverted.push(step),
+                Err(e) => {
+                    steps.push(step);
+                    self.undo_stack.extend(Operation::from_steps(steps));
+                    reverted.reverse();
+                    self.redo_stack.extend(Operation::from_steps(reverted));
+                    return Err(e);
+                }
+            }
+        }
+        reverted.reverse();
+        self.redo_stack.extend(Operation::from_steps(reverted));
+        Ok(Some(description))
+    }
+
+    /// Re-applies the most recently undone operation, returning a description of it. When
+    /// that fails partway, what was re-applied can be undone and the rest stays on the redo stack.
+    pub fn redo(&mut self, mode: &DeleteMode) -> Result<Option<String>> {
+        let Some(operation) = self.redo_stack.pop() else {
+            return Ok(None);
+        };
+        let description = operation.describe();
+
+        let mut steps = operation.into_steps().into_iter();
+        let mut reapplied = Vec::new();
+        while let Some(step) = steps.next() {
+            match reapply(step.clone(), mode) {
+                Ok(step) => reapplied.push(step),
+                Err(e) => {
+                    let remaining: Vec<Operation> = std::iter::once(step).chain(steps).collect();
+                    self.redo_stack.extend(Operation::from_steps(remaining));
+                    self.undo_stack.extend(Operation::from_steps(reapplied));
+                    return Err(e);
+                }
+            }
+        }
+        self.undo_stack.extend(Operation::from_steps(reapplied));
+        Ok(Some(description))
+    }
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+// Undoing a creation always goes through the trash, whatever the delete mode: the user
+// may have put things into a created directory since
+fn revert(operation: Operation) -> Result<Operation> {
+    match &operation {
+        Operation::Copy(pairs) => {
+            for (_, copy) in pairs {
+                crate::trash::move_to_trash(copy)?;
+            }
+        }
+        Operation::Move(pairs) => {
+            for (original, moved) in pairs.iter().rev() {
+                ensure_free(original)?;
+                move_path(moved, original)?;
+            }
+        }
+        Operation::Rename { from, to } => {
+            ensure_free(from)?;
+            fs::rename(to, from)?;
+        }
+        Operation::BulkRename(plan) => {
+            plan.inverse().apply()?;
+        }
+        Operation::Create { created, .. } => {
+            // The outermost created directory holds everything made along with it
+            if let Some(outermost) = created.first() {
+                crate::trash::move_to_trash(outermost)?;
+            }
+        }
+        Operation::Compress { archive, .. } => {
+            crate::trash::move_to_trash(archive)?;
+        }
+        Operation::Extract(pairs) => {
+            for (_, dir) in pairs {
+                crate::trash::move_to_trash(dir)?;
+            }
+        }
+        Operation::Delete(trashed) => {
+            for item in trashed {
+                crate::trash::restore(item)?;
+            }
+        }
+        Operation::Restore(restored) => {
+            // Trashing again yields new trash locations, so the operation is rebuilt
+            let mut retrashed = Vec::new();
+            for item in restored.iter().rev() {
+                retrashed.push(crate::trash::move_to_trash(&item.original_path)?);
+            }
+            retrashed.reverse();
+            return Ok(Operation::Restore(retrashed));
+        }
+        Operation::Batch(operations) => {
+            let mut reverted = Vec::new();
+            for operation in operations.iter().rev() {
+                reverted.push(revert(operation.clone())?);
+            }
+            reverted.reverse();
+            return Ok(Operation::Batch(reverted));
+        }
+    }
+    Ok(operation)
+}
+
+fn reapply(operation: Operation, mode: &DeleteMode) -> Result<Operation> {
+    match operation {
+        Operation::Copy(pairs) => {
+            for (source, copy) in &pairs {
+                ensure_free(copy)?;
+                copy_path(source, copy)?;
+            }
+            Ok(Operation::Copy(pairs))
+        }
+        Operation::Move(pairs) => {
+            for (original, moved) in &pairs {
+                ensure_free(moved)?;
+                move_path(original, moved)?;
+            }
+            Ok(Operation::Move(pairs))
+        }
+        Operation::Rename { from, to } => {
+            ensure_free(&to)?;
+            fs::rename(&from, &to)?;
+            Ok(Operation::Rename { from, to })
+        }
+        Operation::BulkRename(plan) => {
+            plan.apply()?;
+            Ok(Operation::BulkRename(plan))
+        }
+        Operation::Create { path, is_dir, .. } => {
+            ensure_free(&path)?;
+            let created = if is_dir {
+                create_directory(&path)?
+            } else {
+                create_file(&path)?;
+                vec![path.clone()]
+            };
+            Ok(Operation::Create { path, is_dir, created })
+        }
+        Operation::Compress { sources, archive } => {
+            ensure_free(&archive)?;
+            crate::archive::create(&sources, &archive, |_, _| true)?;
+            Ok(Operation::Compress { sources, archive })
+        }
+        Operation::Extract(pairs) => {
+            for (archive, dir) in &pairs {
+                ensure_free(dir)?;
+                crate::archive::Archive::open(archive)?.extract(archive, dir, |_, _| true)?;
+            }
+            Ok(Operation::Extract(pairs))
+        }
+        Operation::Delete(trashed) => {
+            // Trashing again yields new trash locations, so the operation is rebuilt
+            let mut retrashed = Vec::new();
+            for item in &trashed {
+                if let Some(item) = delete_path(&item.original_path, mode)? {
+                    retrashed.push(item);
+                }
+            }
+            Ok(Operation::Delete(retrashed))
+        }
+        Operation::Restore(restored) => {
+            for item in &restored {
+                crate::trash::restore(item)?;
+            }
+            Ok(Operation::Restore(restored))
+        }
+        Operation::Batch(operations) => {
+            let mut reapplied = Vec::new();
+            for operation in operations {
+                reapplied.push(reapply(operation, mode)?);
+            }
+            Ok(Operation::Batch(reapplied))
+        }
+    }
+}
+
+fn ensure_free(path: &Path) -> Result<()> {
+    if fs::symlink_metadata(path).is_ok() {
+        anyhow::bail!("{} already exists", path.display());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictResolution {
+    Overwrite,
+    Skip,
+    Rename,
+    OverwriteIfNewer,
+}
+
+/// A paste that is being planned, possibly waiting for the user to resolve a conflict
+#[derive(Debug)]
+pub struct PendingPaste {
+    pub kind: JobKind,
+    pub dest: PathBuf,
+    pub remaining: Vec<PathBuf>, // first entry is the one currently in conflict
+    pub plan: Vec<Transfer>,
+    pub apply_to_all: Option<ConflictResolution>,
+}
+
 pub fn paste(app: &mut App) -> Result<()> {
-    let dest = &app.current_dir;
+    let dest = app.tab.current_dir.clone();
 
-    match &app.clipboard {
+    match app.clipboard.clone() {
         ClipboardOperation::Copy(paths) => {
-            copy_items(paths, dest)?;
+            copy_items(app, &paths, &dest);
         }
         ClipboardOperation::Cut(paths) => {
-            move_items(paths, dest)?;
-            app.clipboard = ClipboardOperation::None;
+            // A refused move keeps the cut, so it can be pasted elsewhere
+            if move_items(app, &paths, &dest) {
+                app.clipboard = ClipboardOperation::None;
+            }
         }
         ClipboardOperation::None => {}
     }
@@ -26,9 +415,11 @@ pub fn paste(app: &mut App) -> Result<()> {
     Ok(())
 }
 
-fn get_unique_path(dest: &Path) -> PathBuf {
+fn get_unique_path(dest: &Path, jobs: &JobQueue) -> PathBuf {
+    let is_taken = |path: &Path| fs::symlink_metadata(path).is_ok() || jobs.is_pending_destination(path);
+
     // If path doesn't exist, use it as-is
-    if !dest.exists() {
+    if !is_taken(dest) {
         return dest.to_path_buf();
     }
 
@@ -57,7 +448,7 @@ fn get_unique_path(dest: &Path) -> PathBuf {
         };
 
         let new_path = parent.join(new_name);
-        if !new_path.exists() {
+        if !is_taken(&new_path) {
             return new_path;
         }
     }
@@ -66,65 +457,256 @@ fn get_unique_path(dest: &Path) -> PathBuf {
     dest.to_path_buf()
 }
 
-fn copy_items(sources: &[std::path::PathBuf], dest: &Path) -> Result<()> {
-    let dir_options = fs_extra::dir::CopyOptions::new();
-    let file_options = fs_extra::file::CopyOptions::new();
+/// Queues a background job copying `sources` into the `dest` directory. False when the copy
+/// was refused or nothing was left to copy; true once a job is queued or a conflict is asked about.
+pub fn copy_items(app: &mut App, sources: &[PathBuf], dest: &Path) -> bool {
+    start_transfer(app, JobKind::Copy, sources, dest)
+}
 
-    for source in sources {
-        if source.is_dir() {
-            let dir_name = source.file_name().unwrap();
-            let initial_dest_path = dest.join(dir_name);
-            // Get unique path to avoid conflicts
-            let dest_path = get_unique_path(&initial_dest_path);
-            dir::copy(source, &dest_path, &dir_options)?;
-        } else {
-            let initial_dest_path = dest.join(source.file_name().unwrap());
-            // Get unique path to avoid conflicts
-            let dest_path = get_unique_path(&initial_dest_path);
-            fs_extra::file::copy(source, &dest_path, &file_options)?;
+/// Queues a background job moving `sources` into the `dest` directory; returns like `copy_items`
+pub fn move_items(app: &mut App, sources: &[PathBuf], dest: &Path) -> bool {
+    start_transfer(app, JobKind::Move, sources, dest)
+}
+
+fn start_transfer(app: &mut App, kind: JobKind, sources: &[PathBuf], dest: &Path) -> bool {
+    if crate::archive::locate(dest).is_some() {
+        app.error_message = Some(String::from("Archives are read-only; cannot paste into one"));
+        return false;
+    }
+    if kind == JobKind::Move && sources.iter().any(|source| crate::archive::locate_entry(source).is_some()) {
+        app.error_message = Some(String::from("Entries cannot be moved out of an archive; copy them instead"));
+        return false;
+    }
+    if let Some(source) = sources.iter().find(|source| dest.starts_with(source)) {
+        app.error_message = Some(format!("Cannot put {} into itself", file_name_of(source)));
+        return false;
+    }
+    app.pending_paste = Some(PendingPaste {
+        kind,
+        dest: dest.to_path_buf(),
+        remaining: sources.to_vec(),
+        plan: Vec::new(),
+        apply_to_all: None,
+    });
+    continue_transfer(app)
+}
+
+/// Queues a background job packing `sources` into the new archive `archive`;
+/// the format follows its name
+pub fn compress_items(app: &mut App, sources: &[PathBuf], archive: &Path) {
+    if crate::archive::Format::of(archive).is_none() {
+        app.error_message = Some(String::from("Archive names must end in .zip, .tar, .tar.gz or .tar.zst"));
+        return;
+    }
+    if archive.exists() || app.jobs.is_pending_destination(archive) {
+        app.error_message = Some(format!("{} already exists", file_name_of(archive)));
+        return;
+    }
+    if sources.iter().any(|source| crate::archive::locate_entry(source).is_some()) {
+        app.error_message = Some(String::from("Copy entries out of an archive before compressing them"));
+        return;
+    }
+    if sources.iter().any(|source| archive.starts_with(source)) {
+        app.error_message = Some(String::from("Cannot put an archive inside what it compresses"));
+        return;
+    }
+    if let Err(e) = crate::archive::check_names(sources) {
+        app.error_message = Some(e.to_string());
+        return;
+    }
+
+    let plan = sources
+        .iter()
+        .map(|source| Transfer {
+            source: source.clone(),
+            dest: archive.to_path_buf(),
+            overwrite: false,
+        })
+        .collect();
+    let delete_mode = app.config.behavior.delete_mode.clone();
+    app.jobs.enqueue(JobKind::Compress, plan, delete_mode);
+}
+
+/// Queues a background job extracting `archive` into a new directory next to it,
+/// named after the archive without its extension
+pub fn extract_archive(app: &mut App, archive: &Path) {
+    let Some(stem) = crate::archive::stem(archive).filter(|_| archive.is_file()) else {
+        app.error_message = Some(format!("{} is not an archive", file_name_of(archive)));
+        return;
+    };
+    let dest = get_unique_path(&archive.with_file_name(stem), &app.jobs);
+
+    let plan = vec![Transfer {
+        source: archive.to_path_buf(),
+        dest,
+        overwrite: false,
+    }];
+    let delete_mode = app.config.behavior.delete_mode.clone();
+    app.jobs.enqueue(JobKind::Extract, plan, delete_mode);
+}
+
+/// Resolves the conflict the user is being asked about and keeps planning the paste
+pub fn resolve_conflict(app: &mut App, resolution: ConflictResolution, apply_to_all: bool) {
+    let Some(pending) = app.pending_paste.as_mut() else {
+        return;
+    };
+    if apply_to_all {
+        pending.apply_to_all = Some(resolution);
+    }
+    if !pending.remaining.is_empty() {
+        let source = pending.remaining.remove(0);
+        if let Some(name) = source.file_name() {
+            let target = pending.dest.join(name);
+            if let Some(transfer) = plan_item(&app.jobs, source, target, resolution) {
+                pending.plan.push(transfer);
+            }
         }
     }
+    continue_transfer(app);
+}
 
-    Ok(())
+pub fn cancel_transfer(app: &mut App) {
+    app.pending_paste = None;
+    app.mode = Mode::Normal;
 }
 
-fn move_items(sources: &[std::path::PathBuf], dest: &Path) -> Result<()> {
-    let dir_options = fs_extra::dir::CopyOptions::new();
-    let file_options = fs_extra::file::CopyOptions::new();
+// Plans items until the next conflict that needs an answer, then enqueues the job.
+// False when the plan came out empty, so no job was queued.
+fn continue_transfer(app: &mut App) -> bool {
+    let Some(mut pending) = app.pending_paste.take() else {
+        return false;
+    };
+    let policy = match app.config.behavior.paste_conflict {
+        PasteConflict::Ask => None,
+        PasteConflict::Overwrite => Some(ConflictResolution::Overwrite),
+        PasteConflict::Skip => Some(ConflictResolution::Skip),
+        PasteConflict::Rename => Some(ConflictResolution::Rename),
+        PasteConflict::Newer => Some(ConflictResolution::OverwriteIfNewer),
+    };
+
+    while let Some(source) = pending.remaining.first().cloned() {
+        let Some(name) = source.file_name() else {
+            pending.remaining.remove(0);
+            continue;
+        };
+        let target = pending.dest.join(name);
 
-    for source in sources {
-        if source.is_dir() {
-            let dir_name = source.file_name().unwrap();
-            let initial_dest_path = dest.join(dir_name);
-            // Get unique path to avoid conflicts
-            let dest_path = get_unique_path(&initial_dest_path);
-            dir::move_dir(source, &dest_path, &dir_options)?;
+        // Pasting onto itself or onto another job's destination always gets a new name.
+        // A symlink counts as taken even when it dangles.
+        let resolution = if fs::symlink_metadata(&target).is_err() {
+            None
+        } else if target == source || app.jobs.is_pending_destination(&target) {
+            Some(ConflictResolution::Rename)
+        } else if let Some(resolution) = pending.apply_to_all.or(policy) {
+            Some(resolution)
         } else {
-            let initial_dest_path = dest.join(source.file_name().unwrap());
-            // Get unique path to avoid conflicts
-            let dest_path = get_unique_path(&initial_dest_path);
-            fs_extra::file::move_file(source, &dest_path, &file_options)?;
+            app.pending_paste = Some(pending);
+            app.mode = Mode::PasteConflict;
+            return true;
+        };
+
+        pending.remaining.remove(0);
+        let transfer = match resolution {
+            Some(resolution) => plan_item(&app.jobs, source, target, resolution),
+            None => Some(Transfer {
+                source,
+                dest: get_unique_path(&target, &app.jobs),
+                overwrite: false,
+            }),
+        };
+        if let Some(transfer) = transfer {
+            pending.plan.push(transfer);
+        }
+    }
+
+    app.mode = Mode::Normal;
+    let queued = !pending.plan.is_empty();
+    let delete_mode = app.config.behavior.delete_mode.clone();
+    app.jobs.enqueue(pending.kind, pending.plan, delete_mode);
+    queued
+}
+
+fn plan_item(
+    jobs: &JobQueue,
+    source: PathBuf,
+    target: PathBuf,
+    resolution: ConflictResolution,
+) -> Option<Transfer> {
+    let overwrite = match resolution {
+        ConflictResolution::Overwrite => true,
+        ConflictResolution::Skip => return None,
+        ConflictResolution::Rename => {
+            let dest = get_unique_path(&target, jobs);
+            return Some(Transfer { source, dest, overwrite: false });
         }
+        ConflictResolution::OverwriteIfNewer => {
+            let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+            match (modified(&source), modified(&target)) {
+                (Some(source_time), Some(target_time)) if source_time > target_time => true,
+                _ => return None,
+            }
+        }
+    };
+    Some(Transfer { source, dest: target, overwrite })
+}
+
+// Copies `source` so that it ends up exactly at `dest_path`
+fn copy_path(source: &Path, dest_path: &Path) -> Result<()> {
+    if let Some(archive) = crate::archive::locate_entry(source) {
+        crate::archive::Archive::open(&archive)?.extract(source, dest_path, |_, _| true)?;
+    } else if source.is_dir() {
+        let dir_options = dir::CopyOptions::new().copy_inside(true);
+        dir::copy(source, dest_path, &dir_options)?;
+    } else {
+        let file_options = fs_extra::file::CopyOptions::new();
+        fs_extra::file::copy(source, dest_path, &file_options)?;
     }
+    Ok(())
+}
 
+// Moves `source` so that it ends up exactly at `dest_path`
+pub fn move_path(source: &Path, dest_path: &Path) -> Result<()> {
+    // A rename keeps symlinks (even dangling ones) as they are; copying is only needed across filesystems
+    if fs::symlink_metadata(dest_path).is_err() && fs::rename(source, dest_path).is_ok() {
+        return Ok(());
+    }
+    if source.is_dir() {
+        let dir_options = dir::CopyOptions::new().copy_inside(true);
+        dir::move_dir(source, dest_path, &dir_options)?;
+    } else {
+        let file_options = fs_extra::file::CopyOptions::new();
+        fs_extra::file::move_file(source, dest_path, &file_options)?;
+    }
     Ok(())
 }
 
+/// Creates an empty file, failing when something already exists at `path`
 pub fn create_file(path: &Path) -> Result<()> {
-    fs::File::create(path)?;
+    fs::OpenOptions::new().write(true).create_new(true).open(path)?;
     Ok(())
 }
 
-pub fn create_directory(path: &Path) -> Result<()> {
+/// Creates `path` and any missing parents, returning the directories created, outermost first
+pub fn create_directory(path: &Path) -> Result<Vec<PathBuf>> {
+    let missing: Vec<PathBuf> = path
+        .ancestors()
+        .take_while(|dir| fs::symlink_metadata(dir).is_err())
+        .map(Path::to_path_buf)
+        .collect();
     fs::create_dir_all(path)?;
-    Ok(())
+    Ok(missing.into_iter().rev().collect())
 }
 
-pub fn delete_path(path: &Path) -> Result<()> {
+/// Deletes `path`, returning where it went when it was moved to the trash
+pub fn delete_path(path: &Path, mode: &DeleteMode) -> Result<Option<TrashItem>> {
+    if *mode == DeleteMode::Trash {
+        return Ok(Some(crate::trash::move_to_trash(path)?));
+    }
+
     if path.is_dir() {
         fs::remove_dir_all(path)?;
     } else {
         fs::remove_file(path)?;
     }
-    Ok(())
+    Ok(None)
 }
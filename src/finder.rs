@@ -0,0 +1,255 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ratatui::widgets::ListState;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+// Candidates are capped to keep ranking responsive
+const MAX_CANDIDATES: usize = 200_000;
+const MAX_RESULTS: usize = 1000;
+// The ranker scores this many candidates before looking for a newer query
+const RANK_CHUNK: usize = 4096;
+// How often results are sent while a long ranking is still running
+const RANK_UPDATE_INTERVAL: Duration = Duration::from_millis(50);
+
+pub struct FinderMatch {
+    pub path: String, // relative to `Finder::root`, directories end with '/'
+    pub positions: Vec<usize>,
+}
+
+/// Recursive fuzzy finder over the tree below `root`. The walk and the ranking run in the
+/// background, so typing never waits for candidates to be scored.
+pub struct Finder {
+    pub root: PathBuf,
+    pub query: String,
+    pub matches: Vec<FinderMatch>, // best first, for `ranked_query`
+    pub total: usize,              // candidates found so far
+    pub list_state: ListState,
+    pub scanning: bool,
+    ranked_query: String,
+    input: std_mpsc::Sender<RankInput>,
+    rx: mpsc::UnboundedReceiver<Ranking>,
+    cancel: Arc<AtomicBool>,
+}
+
+enum RankInput {
+    Candidate(String),
+    ScanDone,
+    Query(String),
+}
+
+// The best matches for `query` among the candidates found so far
+struct Ranking {
+    query: String,
+    matches: Vec<FinderMatch>,
+    total: usize,
+    scanning: bool,
+}
+
+impl Ranking {
+    fn new(query: &str, ranked: &[(usize, i64, Vec<usize>)], candidates: &[String], scanning: bool) -> Self {
+        Self {
+            query: query.to_string(),
+            matches: ranked
+                .iter()
+                .map(|(candidate, _, positions)| FinderMatch {
+                    path: candidates[*candidate].clone(),
+                    positions: positions.clone(),
+                })
+                .collect(),
+            total: candidates.len(),
+            scanning,
+        }
+    }
+}
+
+impl Finder {
+    pub fn start(root: PathBuf, show_hidden: bool) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (input, inputs) = std_mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let walk_root = root.clone();
+        let walk_cancel = cancel.clone();
+        let walk_input = input.clone();
+        tokio::task::spawn_blocking(move || {
+            let count = AtomicUsize::new(0);
+            crate::walker::walk(&walk_root, show_hidden, &walk_cancel, |path, is_dir| {
+                if let Ok(relative) = path.strip_prefix(&walk_root) {
+                    let mut candidate = relative.to_string_lossy().to_string();
+                    if is_dir {
+                        candidate.push('/');
+                    }
+                    if walk_input.send(RankInput::Candidate(candidate)).is_err() {
+                        return false;
+                    }
+                }
+                count.fetch_add(1, Ordering::Relaxed) + 1 < MAX_CANDIDATES
+            });
+            let _ = walk_input.send(RankInput::ScanDone);
+        });
+
+        let rank_cancel = cancel.clone();
+        tokio::task::spawn_blocking(move || rank(inputs, tx, &rank_cancel));
+
+        Self {
+            root,
+            query: String::new(),
+            matches: Vec::new(),
+            total: 0,
+            list_state: ListState::default(),
+            scanning: true,
+            ranked_query: String::new(),
+            input,
+            rx,
+            cancel,
+        }
+    }
+
+    /// Takes the latest ranking for the current query
+    pub fn check_updates(&mut self) {
+        let mut latest = None;
+        loop {
+            match self.rx.try_recv() {
+                Ok(ranking) => latest = Some(ranking),
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.scanning = false;
+                    break;
+                }
+            }
+        }
+
+        // Rankings for a query that has been typed over since are dropped
+        let Some(ranking) = latest.filter(|ranking| ranking.query == self.query) else {
+            return;
+        };
+        let new_query = ranking.query != self.ranked_query;
+        self.ranked_query = ranking.query;
+        self.matches = ranking.matches;
+        self.total = ranking.total;
+        self.scanning = ranking.scanning;
+
+        let selected = match self.list_state.selected() {
+            _ if self.matches.is_empty() => None,
+            Some(i) if !new_query => Some(i.min(self.matches.len() - 1)),
+            _ => Some(0),
+        };
+        self.list_state.select(selected);
+    }
+
+    /// Asks for the candidates to be ranked against `query`; the current matches stay until
+    /// the new ranking arrives
+    pub fn set_query(&mut self, query: String) {
+        self.query = query.clone();
+        let _ = self.input.send(RankInput::Query(query));
+    }
+
+    pub fn next(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            self.list_state.select(Some((i + 1).min(self.matches.len().saturating_sub(1))));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            self.list_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        let found = self.matches.get(self.list_state.selected()?)?;
+        Some(self.root.join(Path::new(&found.path)))
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Finder {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+// Runs on a worker: collects candidates from the walk and ranks them against the newest
+// query, starting over whenever the query changes. Stops once the finder is dropped.
+fn rank(inputs: std_mpsc::Receiver<RankInput>, tx: mpsc::UnboundedSender<Ranking>, cancel: &AtomicBool) {
+    let matcher = SkimMatcherV2::default();
+    let mut candidates: Vec<String> = Vec::new();
+    let mut query = String::new();
+    let mut scanning = true;
+    let mut scored = 0; // candidates ranked against `query` so far
+    let mut ranked: Vec<(usize, i64, Vec<usize>)> = Vec::new(); // (candidate, score, positions)
+    let mut changed = true;
+    let mut last_sent = Instant::now();
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // Wait for input only when everything is ranked and sent
+        let caught_up = scored == candidates.len();
+        if caught_up && changed {
+            if tx.send(Ranking::new(&query, &ranked, &candidates, scanning)).is_err() {
+                return;
+            }
+            changed = false;
+            last_sent = Instant::now();
+        }
+        let first = if caught_up {
+            match inputs.recv() {
+                Ok(input) => Some(input),
+                Err(_) => return,
+            }
+        } else {
+            None
+        };
+        for input in first.into_iter().chain(inputs.try_iter()) {
+            match input {
+                RankInput::Candidate(candidate) => candidates.push(candidate),
+                RankInput::ScanDone => scanning = false,
+                RankInput::Query(new_query) => {
+                    query = new_query;
+                    scored = 0;
+                    ranked.clear();
+                }
+            }
+            changed = true;
+        }
+
+        // Score the next chunk and merge it into the best matches
+        let end = (scored + RANK_CHUNK).min(candidates.len());
+        for (offset, candidate) in candidates[scored..end].iter().enumerate() {
+            if query.is_empty() {
+                // Without a query, show the first candidates in walk order
+                if ranked.len() < MAX_RESULTS {
+                    ranked.push((scored + offset, 0, Vec::new()));
+                }
+            } else if let Some((score, positions)) = matcher.fuzzy_indices(candidate, &query) {
+                ranked.push((scored + offset, score, positions));
+            }
+        }
+        scored = end;
+
+        // Best score first, shorter paths break ties
+        ranked.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| candidates[a.0].len().cmp(&candidates[b.0].len()))
+        });
+        ranked.truncate(MAX_RESULTS);
+
+        // A long ranking shows its progress now and then
+        if scored < candidates.len() && last_sent.elapsed() >= RANK_UPDATE_INTERVAL {
+            if tx.send(Ranking::new(&query, &ranked, &candidates, scanning)).is_err() {
+                return;
+            }
+            last_sent = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use directories::ProjectDirs;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Once the ranks add up to more than this, they are all aged and the stale entries dropped
+const MAX_TOTAL_RANK: f64 = 10_000.0;
+const AGING_FACTOR: f64 = 0.9;
+const MIN_RANK: f64 = 1.0;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct Entry {
+    path: PathBuf,
+    rank: f64,
+    last_accessed: u64, // seconds since the Unix epoch
+}
+
+impl Entry {
+    // Frequency weighted by how recently the directory was visited, like zoxide
+    fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_accessed);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrecencyMatch {
+    pub path: PathBuf,
+    pub score: f64,
+    pub positions: Vec<usize>, // matched character positions in the path
+}
+
+/// Visited directories ranked by frequency and recency
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct Frecency {
+    #[serde(default)]
+    dirs: Vec<Entry>,
+    #[serde(skip)]
+    visits: Vec<Entry>, // this session's visits, merged into the file on save
+}
+
+impl Frecency {
+    /// Loads the database; a missing file means nothing has been visited yet
+    pub fn load() -> Result<Self> {
+        let path = Self::get_db_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Adds this session's visits to the database on disk. It is read again first, so other
+    /// sessions' visits are kept, and a file that does not load is left alone rather than replaced.
+    pub fn save(&self) -> Result<()> {
+        if self.visits.is_empty() {
+            return Ok(());
+        }
+        let mut saved = Self::load()?;
+        for visit in &self.visits {
+            saved.visit(&visit.path, visit.rank, visit.last_accessed);
+        }
+        crate::file_ops::write_atomic(&Self::get_db_path()?, &toml::to_string_pretty(&saved)?)
+    }
+
+    /// Records a visit to `dir`. Paths that are not valid UTF-8 cannot be stored in TOML and are
+    /// not recorded.
+    pub fn add(&mut self, dir: &Path) {
+        if dir.to_str().is_none() {
+            return;
+        }
+        let now = now();
+        self.visit(dir, 1.0, now);
+        match self.visits.iter_mut().find(|visit| visit.path == dir) {
+            Some(visit) => {
+                visit.rank += 1.0;
+                visit.last_accessed = now;
+            }
+            None => self.visits.push(Entry {
+                path: dir.to_path_buf(),
+                rank: 1.0,
+                last_accessed: now,
+            }),
+        }
+    }
+
+    // Adds `rank` to the entry for `dir`, then ages all entries when the total grows too large
+    fn visit(&mut self, dir: &Path, rank: f64, last_accessed: u64) {
+        match self.dirs.iter_mut().find(|entry| entry.path == dir) {
+            Some(entry) => {
+                entry.rank += rank;
+                entry.last_accessed = entry.last_accessed.max(last_accessed);
+            }
+            None => self.dirs.push(Entry {
+                path: dir.to_path_buf(),
+                rank,
+                last_accessed,
+            }),
+        }
+
+        let total: f64 = self.dirs.iter().map(|entry| entry.rank).sum();
+        if total > MAX_TOTAL_RANK {
+            for entry in &mut self.dirs {
+                entry.rank *= AGING_FACTOR;
+            }
+            self.dirs.retain(|entry| entry.rank >= MIN_RANK);
+        }
+    }
+
+    /// Existing directories matching every whitespace-separated keyword, best first.
+    /// The last keyword has to match the final path component, so `proj` finds
+    /// `~/Projects` rather than everything below it.
+    pub fn query(&self, query: &str, exclude: Option<&Path>) -> Vec<FrecencyMatch> {
+        let matcher = SkimMatcherV2::default();
+        let keywords: Vec<&str> = query.split_whitespace().collect();
+        let now = now();
+
+        let mut matches: Vec<FrecencyMatch> = self.dirs
+            .iter()
+            .filter(|entry| Some(entry.path.as_path()) != exclude)
+            .filter_map(|entry| {
+                let path = entry.path.to_string_lossy();
+                let mut positions = Vec::new();
+                for keyword in &keywords {
+                    let (_score, found) = matcher.fuzzy_indices(&path, keyword)?;
+                    positions.extend(found);
+                }
+                if let Some(last) = keywords.last() {
+                    let name = entry.path.file_name()?.to_string_lossy();
+                    matcher.fuzzy_match(&name, last)?;
+                }
+                positions.sort_unstable();
+                positions.dedup();
+                Some(FrecencyMatch {
+                    path: entry.path.clone(),
+                    score: entry.score(now),
+                    positions,
+                })
+            })
+            .filter(|found| found.path.is_dir())
+            .collect();
+
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches
+    }
+
+    fn get_db_path() -> Result<PathBuf> {
+        if let Some(proj_dirs) = ProjectDirs::from("", "", "jumper") {
+            Ok(proj_dirs.data_dir().join("frecency.toml"))
+        } else {
+            anyhow::bail!("Could not determine data directory")
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
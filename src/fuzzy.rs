@@ -1,30 +1,53 @@
-use crate::app::App;
+use crate::app::{App, FileEntry};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 
 pub fn update_search(app: &mut App) {
-    if app.search_query.is_empty() {
-        app.search_highlights.clear();
-        app.search_match_positions.clear();
+    refresh_search(app);
+
+    // Jump to first match if any
+    if let Some(&first) = app.tab.search_highlights.first() {
+        let position = app.tab.filtered_indices.iter().position(|&i| i == first);
+        app.tab.list_state.select(position);
+    }
+}
+
+/// Recomputes the highlighted matches for the current query without moving the cursor
+pub fn refresh_search(app: &mut App) {
+    app.tab.search_highlights.clear();
+    app.tab.search_match_positions.clear();
+    if app.tab.search_query.is_empty() {
         return;
     }
 
     let matcher = SkimMatcherV2::default();
-    let mut matching_indices = Vec::new();
-
-    app.search_match_positions.clear();
 
-    for (i, file) in app.files.iter().enumerate() {
-        if let Some((_score, positions)) = matcher.fuzzy_indices(&file.name, &app.search_query) {
-            matching_indices.push(i);
-            app.search_match_positions.insert(i, positions);
+    // Only entries visible through the filter can match, in list order
+    for &i in &app.tab.filtered_indices {
+        let file = &app.tab.files[i];
+        if let Some((_score, positions)) = matcher.fuzzy_indices(&file.name, &app.tab.search_query) {
+            app.tab.search_highlights.push(i);
+            app.tab.search_match_positions.insert(i, positions);
         }
     }
+}
 
-    app.search_highlights = matching_indices;
+/// Returns the indices of `files` matching `query`, best score first, with their matched positions
+pub fn filter_files(files: &[FileEntry], query: &str) -> (Vec<usize>, HashMap<usize, Vec<usize>>) {
+    let matcher = SkimMatcherV2::default();
+    let mut scored = Vec::new();
+    let mut match_positions = HashMap::new();
 
-    // Jump to first match if any
-    if !app.search_highlights.is_empty() {
-        app.list_state.select(Some(app.search_highlights[0]));
+    for (i, file) in files.iter().enumerate() {
+        if let Some((score, positions)) = matcher.fuzzy_indices(&file.name, query) {
+            scored.push((score, i));
+            match_positions.insert(i, positions);
+        }
     }
+
+    // Equal scores keep the current sort order
+    scored.sort_by_key(|&(score, i)| (Reverse(score), i));
+    (scored.into_iter().map(|(_, i)| i).collect(), match_positions)
 }
@@ -0,0 +1,200 @@
+use ratatui::widgets::ListState;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// Limits that keep a search over a large tree responsive
+const MAX_MATCHES: usize = 10_000;
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+const MAX_SNIPPET_CHARS: usize = 200;
+const SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct GrepMatch {
+    pub path: PathBuf,         // relative to the search root
+    pub line_number: usize,    // 1-based
+    pub snippet: String,       // the matching line with leading whitespace trimmed
+    pub column: Option<usize>, // byte offset of the match in `snippet`, if it fits
+}
+
+/// Content search over the files below `root`
+pub struct Grep {
+    pub root: PathBuf,
+    pub query: String,
+    pub matches: Vec<GrepMatch>,
+    pub list_state: ListState,
+    pub files_searched: usize,
+    pub scanning: bool,
+    rx: mpsc::UnboundedReceiver<GrepUpdate>,
+    cancel: Arc<AtomicBool>,
+}
+
+enum GrepUpdate {
+    File(Vec<GrepMatch>),
+    Skipped,
+}
+
+impl Grep {
+    pub fn start(root: PathBuf, query: String, show_hidden: bool) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let walk_root = root.clone();
+        let walk_cancel = cancel.clone();
+        let pattern = Pattern::new(&query);
+        tokio::task::spawn_blocking(move || {
+            let found = AtomicUsize::new(0);
+            crate::walker::walk(&walk_root, show_hidden, &walk_cancel, |path, is_dir| {
+                if is_dir {
+                    return true;
+                }
+                let relative = path.strip_prefix(&walk_root).unwrap_or(path);
+                let update = match search_file(path, relative, &pattern, &walk_cancel) {
+                    Some(matches) => {
+                        found.fetch_add(matches.len(), Ordering::Relaxed);
+                        GrepUpdate::File(matches)
+                    }
+                    None => GrepUpdate::Skipped,
+                };
+                tx.send(update).is_ok() && found.load(Ordering::Relaxed) < MAX_MATCHES
+            });
+            // Dropping the sender tells the grep the search is complete
+        });
+
+        Self {
+            root,
+            query,
+            matches: Vec::new(),
+            list_state: ListState::default(),
+            files_searched: 0,
+            scanning: true,
+            rx,
+            cancel,
+        }
+    }
+
+    /// Collects matches found since the last call
+    pub fn check_updates(&mut self) {
+        loop {
+            match self.rx.try_recv() {
+                Ok(GrepUpdate::File(matches)) => {
+                    self.files_searched += 1;
+                    self.matches.extend(matches);
+                }
+                Ok(GrepUpdate::Skipped) => {
+                    self.files_searched += 1;
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.scanning = false;
+                    break;
+                }
+            }
+        }
+
+        if self.list_state.selected().is_none() && !self.matches.is_empty() {
+            self.list_state.select(Some(0));
+        }
+    }
+
+    pub fn next(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            self.list_state.select(Some((i + 1).min(self.matches.len().saturating_sub(1))));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            self.list_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        let found = self.matches.get(self.list_state.selected()?)?;
+        Some(self.root.join(&found.path))
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Grep {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Literal pattern; case-insensitive unless the query contains uppercase letters
+struct Pattern {
+    needle: String,
+    ignore_case: bool,
+}
+
+impl Pattern {
+    fn new(query: &str) -> Self {
+        let ignore_case = !query.chars().any(|c| c.is_uppercase());
+        let needle = if ignore_case { query.to_ascii_lowercase() } else { query.to_string() };
+        Self { needle, ignore_case }
+    }
+
+    // ASCII case folding keeps byte offsets valid in the original line
+    fn find(&self, line: &str) -> Option<usize> {
+        if self.ignore_case {
+            line.to_ascii_lowercase().find(&self.needle)
+        } else {
+            line.find(&self.needle)
+        }
+    }
+}
+
+// Returns None for files that were skipped (binary, too large or unreadable)
+fn search_file(path: &Path, relative: &Path, pattern: &Pattern, cancel: &AtomicBool) -> Option<Vec<GrepMatch>> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+
+    let mut reader = BufReader::new(fs::File::open(path).ok()?);
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    (&mut reader).take(SNIFF_BYTES as u64).read_to_end(&mut head).ok()?;
+    if crate::preview::is_binary(&head) {
+        return None;
+    }
+
+    let mut matches = Vec::new();
+    let mut reader = head.as_slice().chain(reader);
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        line_number += 1;
+
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\n', '\r']).trim_start();
+        let Some(offset) = pattern.find(text) else {
+            continue;
+        };
+
+        // Long lines are cut; the highlight is dropped when the match falls outside
+        let snippet: String = text.chars().take(MAX_SNIPPET_CHARS).collect();
+        let column = (offset + pattern.needle.len() <= snippet.len()).then_some(offset);
+        matches.push(GrepMatch {
+            path: relative.to_path_buf(),
+            line_number,
+            snippet,
+            column,
+        });
+    }
+    Some(matches)
+}
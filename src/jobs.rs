@@ -0,0 +1,523 @@
+use crate::archive::Archive;
+use crate::config::DeleteMode;
+use crate::trash::TrashItem;
+use anyhow::Result;
+use std::fs;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    Compress, // every source goes into the single archive named as destination
+    Extract,  // each source is an archive, unpacked into a new directory
+}
+
+/// A single item to transfer; `overwrite` replaces an existing destination
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+}
+
+impl Progress {
+    pub fn ratio(&self) -> f64 {
+        if self.bytes_total > 0 {
+            (self.bytes_done as f64 / self.bytes_total as f64).min(1.0)
+        } else if self.files_total > 0 {
+            (self.files_done as f64 / self.files_total as f64).min(1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+pub struct Job {
+    pub id: usize,
+    pub kind: JobKind,
+    pub description: String,
+    pub progress: Progress,
+    destinations: Vec<PathBuf>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+/// Result of a job that has stopped running
+pub struct FinishedJob {
+    pub kind: JobKind,
+    pub description: String,
+    pub completed: Vec<(PathBuf, PathBuf)>, // (source, destination) pairs that fully transferred
+    pub replaced: Vec<TrashItem>,           // overwritten destinations that went to the trash
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
+#[derive(Default)]
+struct Outcome {
+    completed: Vec<(PathBuf, PathBuf)>,
+    replaced: Vec<TrashItem>,
+}
+
+enum JobUpdate {
+    Progress(usize, Progress),
+    Finished(usize, Outcome, Option<String>),
+}
+
+pub struct JobQueue {
+    pub jobs: Vec<Job>,
+    next_id: usize,
+    tx: mpsc::UnboundedSender<JobUpdate>,
+    rx: mpsc::UnboundedReceiver<JobUpdate>,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            jobs: Vec::new(),
+            next_id: 1,
+            tx,
+            rx,
+        }
+    }
+
+    /// Starts transferring each item on a blocking worker thread
+    pub fn enqueue(&mut self, kind: JobKind, plan: Vec<Transfer>, delete_mode: DeleteMode) {
+        if plan.is_empty() {
+            return;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let name_of = |path: &Path| path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let what = if plan.len() == 1 {
+            name_of(&plan[0].source)
+        } else {
+            format!("{} items", plan.len())
+        };
+        let dest_dir = plan[0].dest
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let description = match kind {
+            JobKind::Copy => format!("Copy {} -> {}", what, dest_dir),
+            JobKind::Move => format!("Move {} -> {}", what, dest_dir),
+            JobKind::Compress => format!("Compress {} -> {}", what, name_of(&plan[0].dest)),
+            JobKind::Extract => format!("Extract {} -> {}", what, dest_dir),
+        };
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.jobs.push(Job {
+            id,
+            kind,
+            description,
+            progress: Progress::default(),
+            destinations: plan.iter().map(|t| t.dest.clone()).collect(),
+            cancel: cancel.clone(),
+        });
+
+        let tx = self.tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut worker = Worker {
+                id,
+                tx: tx.clone(),
+                cancel,
+                progress: Progress::default(),
+                last_report: Instant::now(),
+                delete_mode,
+            };
+            let mut outcome = Outcome::default();
+            let error = worker.run(kind, &plan, &mut outcome).err().map(|e| e.to_string());
+            let _ = tx.send(JobUpdate::Finished(id, outcome, error));
+        });
+    }
+
+    pub fn cancel(&mut self, id: usize) {
+        if let Some(job) = self.jobs.iter().find(|j| j.id == id) {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether a running job is about to create `path`
+    pub fn is_pending_destination(&self, path: &Path) -> bool {
+        self.jobs.iter().any(|j| j.destinations.iter().any(|d| d == path))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Combined progress of all running jobs
+    pub fn total_progress(&self) -> Progress {
+        let mut total = Progress::default();
+        for job in &self.jobs {
+            total.bytes_done += job.progress.bytes_done;
+            total.bytes_total += job.progress.bytes_total;
+            total.files_done += job.progress.files_done;
+            total.files_total += job.progress.files_total;
+        }
+        total
+    }
+
+    /// Applies pending progress updates and returns the jobs that finished since the last call
+    pub fn poll(&mut self) -> Vec<FinishedJob> {
+        let mut finished = Vec::new();
+
+        while let Ok(update) = self.rx.try_recv() {
+            match update {
+                JobUpdate::Progress(id, progress) => {
+                    if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                        job.progress = progress;
+                    }
+                }
+                JobUpdate::Finished(id, outcome, error) => {
+                    if let Some(pos) = self.jobs.iter().position(|j| j.id == id) {
+                        let job = self.jobs.remove(pos);
+                        finished.push(FinishedJob {
+                            kind: job.kind,
+                            cancelled: job.is_cancelled(),
+                            description: job.description,
+                            completed: outcome.completed,
+                            replaced: outcome.replaced,
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+
+        finished
+    }
+}
+
+struct Worker {
+    id: usize,
+    tx: mpsc::UnboundedSender<JobUpdate>,
+    cancel: Arc<AtomicBool>,
+    progress: Progress,
+    last_report: Instant,
+    delete_mode: DeleteMode,
+}
+
+impl Worker {
+    fn run(
+        &mut self,
+        kind: JobKind,
+        plan: &[Transfer],
+        outcome: &mut Outcome,
+    ) -> Result<()> {
+        match kind {
+            JobKind::Compress => return self.compress(plan, outcome),
+            JobKind::Extract => return self.unpack(plan, outcome),
+            JobKind::Copy | JobKind::Move => {}
+        }
+
+        // Entries copied out of an archive are extracted from it, each archive is read once
+        let mut archives: HashMap<PathBuf, Archive> = HashMap::new();
+        for transfer in plan {
+            if let Some(path) = crate::archive::locate_entry(&transfer.source) {
+                if !archives.contains_key(&path) {
+                    archives.insert(path.clone(), Archive::open(&path)?);
+                }
+            }
+        }
+        let archive_of = |source: &Path| archives.values().find(|archive| source.starts_with(&archive.path) && source != archive.path);
+
+        // Measure everything up front so the gauge has a stable total
+        for transfer in plan {
+            if let Some(archive) = archive_of(&transfer.source) {
+                let (files, bytes) = archive.totals(&transfer.source);
+                self.progress.files_total += files;
+                self.progress.bytes_total += bytes;
+                continue;
+            }
+            self.measure(&transfer.source);
+        }
+        self.report(true);
+
+        for Transfer { source, dest, overwrite } in plan {
+            if self.cancelled() {
+                break;
+            }
+
+            if source.starts_with(dest) || dest.starts_with(source) {
+                anyhow::bail!("Cannot put {} into itself or over its own folder", source.display());
+            }
+            let replacing = fs::symlink_metadata(dest).is_ok();
+            if replacing && !*overwrite {
+                anyhow::bail!("{} already exists", dest.display());
+            }
+
+            // A replacement is written next to the old destination, which is only removed once
+            // the new one is complete, so a failed or cancelled transfer loses neither
+            let target = if replacing { staging_path(dest) } else { dest.clone() };
+            if !self.transfer(kind, archive_of(source), source, &target)? {
+                break;
+            }
+            if replacing {
+                // Replaced items go through the regular delete path so they can be restored
+                let left_at = |e: anyhow::Error| anyhow::anyhow!("{} (the new copy is at {})", e, target.display());
+                if let Some(item) = crate::file_ops::delete_path(dest, &self.delete_mode).map_err(left_at)? {
+                    outcome.replaced.push(item);
+                }
+                fs::rename(&target, dest).map_err(|e| left_at(e.into()))?;
+            }
+            outcome.completed.push((source.clone(), dest.clone()));
+        }
+
+        self.report(true);
+        Ok(())
+    }
+
+    /// Packs the sources of `plan` into the archive that is the destination of every item
+    fn compress(&mut self, plan: &[Transfer], outcome: &mut Outcome) -> Result<()> {
+        let archive = &plan[0].dest;
+        let sources: Vec<PathBuf> = plan.iter().map(|t| t.source.clone()).collect();
+        for source in &sources {
+            self.measure(source);
+        }
+        self.report(true);
+
+        if fs::symlink_metadata(archive).is_ok() {
+            anyhow::bail!("{} already exists", archive.display());
+        }
+        let result = crate::archive::create(&sources, archive, |bytes, files| {
+            self.progress.bytes_done += bytes;
+            self.progress.files_done += files;
+            self.report(false);
+            !self.cancelled()
+        });
+        match result {
+            Ok(true) => outcome.completed = sources.into_iter().map(|s| (s, archive.clone())).collect(),
+            Ok(false) => remove_partial(archive),
+            Err(e) => {
+                remove_partial(archive);
+                return Err(e);
+            }
+        }
+
+        self.report(true);
+        Ok(())
+    }
+
+    /// Extracts each archive of `plan` into its destination directory
+    fn unpack(&mut self, plan: &[Transfer], outcome: &mut Outcome) -> Result<()> {
+        let archives = plan
+            .iter()
+            .map(|transfer| Archive::open(&transfer.source))
+            .collect::<Result<Vec<_>>>()?;
+        for archive in &archives {
+            let (files, bytes) = archive.totals(&archive.path);
+            self.progress.files_total += files;
+            self.progress.bytes_total += bytes;
+        }
+        self.report(true);
+
+        for (Transfer { source, dest, .. }, archive) in plan.iter().zip(&archives) {
+            if self.cancelled() {
+                break;
+            }
+            if fs::symlink_metadata(dest).is_ok() {
+                anyhow::bail!("{} already exists", dest.display());
+            }
+            match self.extract(archive, source, dest) {
+                Ok(true) => outcome.completed.push((source.clone(), dest.clone())),
+                Ok(false) => {
+                    remove_partial(dest);
+                    break;
+                }
+                Err(e) => {
+                    remove_partial(dest);
+                    return Err(e);
+                }
+            }
+        }
+
+        self.report(true);
+        Ok(())
+    }
+
+    // Adds the files below `path` to the totals
+    fn measure(&mut self, path: &Path) {
+        for entry in WalkDir::new(path).into_iter().flatten() {
+            if entry.file_type().is_file() {
+                self.progress.files_total += 1;
+                self.progress.bytes_total += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+    }
+
+    /// Copies, moves or extracts `source` to `target`, which must not exist yet.
+    /// Returns false when cancelled; a partial result is removed either way.
+    fn transfer(&mut self, kind: JobKind, archive: Option<&Archive>, source: &Path, target: &Path) -> Result<bool> {
+        let result = match archive {
+            Some(_) if kind == JobKind::Move => {
+                anyhow::bail!("Cannot move {} out of an archive", source.display());
+            }
+            Some(archive) => self.extract(archive, source, target),
+            None => {
+                // Moving within a filesystem is a cheap rename
+                if kind == JobKind::Move && fs::rename(source, target).is_ok() {
+                    self.count_finished(target);
+                    return Ok(true);
+                }
+                self.copy_tree(source, target)
+            }
+        };
+
+        match result {
+            Ok(true) => {
+                if kind == JobKind::Move {
+                    if source.is_dir() {
+                        fs::remove_dir_all(source)?;
+                    } else {
+                        fs::remove_file(source)?;
+                    }
+                }
+                Ok(true)
+            }
+            Ok(false) => {
+                // Cancelled midway: drop the partial copy
+                remove_partial(target);
+                Ok(false)
+            }
+            Err(e) => {
+                remove_partial(target);
+                Err(e)
+            }
+        }
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn report(&mut self, force: bool) {
+        if force || self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.last_report = Instant::now();
+            let _ = self.tx.send(JobUpdate::Progress(self.id, self.progress.clone()));
+        }
+    }
+
+    fn count_finished(&mut self, path: &Path) {
+        for entry in WalkDir::new(path).into_iter().flatten() {
+            if entry.file_type().is_file() {
+                self.progress.files_done += 1;
+                self.progress.bytes_done += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+        self.report(false);
+    }
+
+    /// Extracts `source` from `archive` to `dest`; returns false when cancelled
+    fn extract(&mut self, archive: &Archive, source: &Path, dest: &Path) -> Result<bool> {
+        archive.extract(source, dest, |bytes, files| {
+            self.progress.bytes_done += bytes;
+            self.progress.files_done += files;
+            self.report(false);
+            !self.cancelled()
+        })
+    }
+
+    /// Copies `source` to `dest`; returns false when cancelled
+    fn copy_tree(&mut self, source: &Path, dest: &Path) -> Result<bool> {
+        let mut dirs = Vec::new();
+        for entry in WalkDir::new(source) {
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(source)?;
+            let target = if relative.as_os_str().is_empty() {
+                dest.to_path_buf()
+            } else {
+                dest.join(relative)
+            };
+            let file_type = entry.file_type();
+
+            if file_type.is_dir() {
+                fs::create_dir_all(&target)?;
+                dirs.push((target, entry.metadata()?.modified()?));
+            } else if file_type.is_symlink() {
+                std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+            } else if !self.copy_file(entry.path(), &target)? {
+                return Ok(false);
+            }
+        }
+
+        // Directory times last, innermost first, since filling a directory touches it
+        for (dir, modified) in dirs.iter().rev() {
+            fs::File::open(dir)?.set_modified(*modified)?;
+        }
+        Ok(true)
+    }
+
+    fn copy_file(&mut self, source: &Path, dest: &Path) -> Result<bool> {
+        let mut reader = fs::File::open(source)?;
+        let mut writer = fs::File::create(dest)?;
+        let mut buffer = vec![0; BUFFER_SIZE];
+
+        loop {
+            if self.cancelled() {
+                return Ok(false);
+            }
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..read])?;
+            self.progress.bytes_done += read as u64;
+            self.report(false);
+        }
+
+        let metadata = fs::metadata(source)?;
+        writer.set_modified(metadata.modified()?)?;
+        fs::set_permissions(dest, metadata.permissions())?;
+        self.progress.files_done += 1;
+        self.report(false);
+        Ok(true)
+    }
+}
+
+// An unused name next to `dest` to build its replacement under
+fn staging_path(dest: &Path) -> PathBuf {
+    let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut attempt = 0;
+    loop {
+        let candidate = dest.with_file_name(format!(".{}.jumper-{}-{}", name, std::process::id(), attempt));
+        if fs::symlink_metadata(&candidate).is_err() {
+            return candidate;
+        }
+        attempt += 1;
+    }
+}
+
+fn remove_partial(path: &Path) {
+    if path.is_dir() {
+        let _ = fs::remove_dir_all(path);
+    } else {
+        let _ = fs::remove_file(path);
+    }
+}
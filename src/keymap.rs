@@ -0,0 +1,399 @@
+use crate::config::KeyBindings;
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Everything a key sequence can be bound to in normal and visual mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    None, // removes a default binding
+    Quit,
+    QuitAndCd,
+    MoveDown,
+    MoveUp,
+    EnterDir,
+    GoParent,
+    GoTop,
+    GoBottom,
+    HistoryBack,
+    ToggleHidden,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    ToggleDualPane,
+    SwitchPane,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    TogglePreview,
+    ToggleMillerColumns,
+    ToggleMark,
+    VisualMode,
+    Yank,
+    YankCurrent,
+    Cut,
+    Paste,
+    ShowJobs,
+    Undo,
+    Redo,
+    Search,
+    Filter,
+    Find,
+    Grep,
+    FrecencyJump,
+    SearchNext,
+    SearchPrevious,
+    SearchCurrentName,
+    SortMenu,
+    ToggleSortOrder,
+    Create,
+    Edit,
+    OpenWith,
+    Rename,
+    RenameFull,
+    BulkRename,
+    PatternRename,
+    Compress,
+    Extract,
+    Delete,
+    ShowTrash,
+    SetBookmark,
+    JumpToBookmark,
+    ShowBookmarks,
+    Help,
+    Confirm,
+    Cancel,
+}
+
+const DEFAULT_NORMAL: &[(&str, Action)] = &[
+    ("q", Action::Quit),
+    ("Q", Action::QuitAndCd),
+    ("j", Action::MoveDown),
+    ("<down>", Action::MoveDown),
+    ("k", Action::MoveUp),
+    ("<up>", Action::MoveUp),
+    ("l", Action::EnterDir),
+    ("<right>", Action::EnterDir),
+    ("h", Action::GoParent),
+    ("<left>", Action::GoParent),
+    ("gg", Action::GoTop),
+    ("G", Action::GoBottom),
+    (".", Action::ToggleHidden),
+    ("t", Action::NewTab),
+    ("<ctrl+w>", Action::CloseTab),
+    ("gt", Action::NextTab),
+    ("gT", Action::PreviousTab),
+    ("|", Action::ToggleDualPane),
+    ("<tab>", Action::SwitchPane),
+    ("<f5>", Action::CopyToOtherPane),
+    ("<f6>", Action::MoveToOtherPane),
+    ("P", Action::TogglePreview),
+    ("M", Action::ToggleMillerColumns),
+    ("m", Action::ToggleMark),
+    ("V", Action::VisualMode),
+    ("y", Action::Yank),
+    ("yy", Action::YankCurrent),
+    ("x", Action::Cut),
+    ("p", Action::Paste),
+    ("J", Action::ShowJobs),
+    ("u", Action::Undo),
+    ("<ctrl+r>", Action::Redo),
+    ("/", Action::Search),
+    ("F", Action::Filter),
+    ("f", Action::Find),
+    ("S", Action::Grep),
+    ("z", Action::FrecencyJump),
+    ("n", Action::SearchNext),
+    ("N", Action::SearchPrevious),
+    ("*", Action::SearchCurrentName),
+    ("s", Action::SortMenu),
+    ("o", Action::ToggleSortOrder),
+    ("a", Action::Create),
+    ("e", Action::Edit),
+    ("O", Action::OpenWith),
+    ("r", Action::Rename),
+    ("R", Action::RenameFull),
+    ("E", Action::BulkRename),
+    ("<ctrl+e>", Action::PatternRename),
+    ("C", Action::Compress),
+    ("X", Action::Extract),
+    ("d", Action::Delete),
+    ("T", Action::ShowTrash),
+    ("b", Action::SetBookmark),
+    ("'", Action::JumpToBookmark),
+    ("B", Action::ShowBookmarks),
+    ("?", Action::Help),
+    ("<esc>", Action::Cancel),
+];
+
+const DEFAULT_VISUAL: &[(&str, Action)] = &[
+    ("j", Action::MoveDown),
+    ("<down>", Action::MoveDown),
+    ("k", Action::MoveUp),
+    ("<up>", Action::MoveUp),
+    ("gg", Action::GoTop),
+    ("G", Action::GoBottom),
+    ("m", Action::ToggleMark),
+    ("y", Action::Yank),
+    ("x", Action::Cut),
+    ("C", Action::Compress),
+    ("d", Action::Delete),
+    ("<enter>", Action::Confirm),
+    ("<esc>", Action::Cancel),
+];
+
+/// A single key press: a key code plus ctrl/alt (shift is folded into the character)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        // Terminals disagree on reporting shift for characters, the character itself is enough
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+
+    /// Parses a single key like `j`, `enter`, `f5` or `ctrl+r`
+    fn parse(text: &str) -> Result<Self> {
+        let (mods, name) = match text.rsplit_once('+') {
+            // A trailing "+" is the plus key itself (e.g. "ctrl++")
+            Some((mods, "")) => (mods.strip_suffix('+').unwrap_or(mods), "+"),
+            Some((mods, name)) => (mods, name),
+            None => ("", text),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in mods.split('+').filter(|p| !p.is_empty()) {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" | "meta" => modifiers |= KeyModifiers::ALT,
+                "shift" => modifiers |= KeyModifiers::SHIFT,
+                other => bail!("unknown modifier \"{}\" in \"{}\"", other, text),
+            }
+        }
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            _ => match name.to_lowercase().as_str() {
+                "enter" | "return" | "cr" => KeyCode::Enter,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "lt" => KeyCode::Char('<'),
+                "backspace" | "bs" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                lower => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                    _ => bail!("unknown key \"{}\"", name),
+                },
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => String::from("space"),
+            KeyCode::Char('<') => String::from("lt"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => return write!(f, "{}", c),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("f{}", n),
+            KeyCode::Enter => String::from("enter"),
+            KeyCode::Tab => String::from("tab"),
+            KeyCode::BackTab => String::from("backtab"),
+            KeyCode::Esc => String::from("esc"),
+            KeyCode::Backspace => String::from("backspace"),
+            KeyCode::Delete => String::from("delete"),
+            KeyCode::Insert => String::from("insert"),
+            KeyCode::Home => String::from("home"),
+            KeyCode::End => String::from("end"),
+            KeyCode::PageUp => String::from("pageup"),
+            KeyCode::PageDown => String::from("pagedown"),
+            KeyCode::Up => String::from("up"),
+            KeyCode::Down => String::from("down"),
+            KeyCode::Left => String::from("left"),
+            KeyCode::Right => String::from("right"),
+            other => format!("{:?}", other).to_lowercase(),
+        };
+
+        write!(f, "<")?;
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        write!(f, "{}>", name)
+    }
+}
+
+/// Parses a key sequence: plain characters are keys of their own and special keys go in
+/// angle brackets, e.g. `gg`, `<ctrl+r>`, `g<tab>` or `<f5>`. A bare chord such as
+/// `ctrl+o` is also accepted for a single key.
+pub fn parse_sequence(text: &str) -> Result<Vec<KeyChord>> {
+    if text.is_empty() {
+        bail!("empty key sequence");
+    }
+    if !text.contains('<') && text.chars().count() > 1 && text.contains('+') {
+        return Ok(vec![KeyChord::parse(text)?]);
+    }
+
+    let mut sequence = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '<' {
+            let mut inner = String::new();
+            let mut closed = false;
+            for c in chars.by_ref() {
+                if c == '>' {
+                    closed = true;
+                    break;
+                }
+                inner.push(c);
+            }
+            if !closed || inner.is_empty() {
+                bail!("unterminated \"<\" in \"{}\"", text);
+            }
+            sequence.push(KeyChord::parse(&inner)?);
+        } else {
+            sequence.push(KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+    Ok(sequence)
+}
+
+pub fn format_sequence(sequence: &[KeyChord]) -> String {
+    sequence.iter().map(|chord| chord.to_string()).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyMatch {
+    Exact,
+    Prefix, // the keys typed so far start a longer binding
+    None,
+}
+
+/// Compares the keys typed so far against a binding
+pub fn matches_keybinding(pending: &[KeyChord], binding: &[KeyChord]) -> KeyMatch {
+    if pending == binding {
+        KeyMatch::Exact
+    } else if binding.len() > pending.len() && binding.starts_with(pending) {
+        KeyMatch::Prefix
+    } else {
+        KeyMatch::None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Target {
+    Action(Action),
+    Jump(PathBuf), // quick jump to a directory
+}
+
+/// Key sequences of one mode and what they trigger
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: HashMap<Vec<KeyChord>, Target>,
+}
+
+impl Keymap {
+    /// Builds the normal mode keymap: defaults, then quick jumps, `history_back` and
+    /// `[keybindings.normal]`. Invalid entries are skipped and reported.
+    pub fn normal(config: &KeyBindings) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut errors = Vec::new();
+
+        for (keys, action) in DEFAULT_NORMAL {
+            keymap.bind(keys, Target::Action(*action), &mut errors);
+        }
+        for (keys, path) in &config.quick_jumps {
+            keymap.bind(keys, Target::Jump(PathBuf::from(path)), &mut errors);
+        }
+        keymap.bind(&config.history_back, Target::Action(Action::HistoryBack), &mut errors);
+        for (keys, action) in &config.normal {
+            keymap.bind(keys, Target::Action(*action), &mut errors);
+        }
+
+        (keymap, errors)
+    }
+
+    /// Builds the visual mode keymap from the defaults and `[keybindings.visual]`
+    pub fn visual(config: &KeyBindings) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut errors = Vec::new();
+
+        for (keys, action) in DEFAULT_VISUAL {
+            keymap.bind(keys, Target::Action(*action), &mut errors);
+        }
+        for (keys, action) in &config.visual {
+            keymap.bind(keys, Target::Action(*action), &mut errors);
+        }
+
+        (keymap, errors)
+    }
+
+    fn bind(&mut self, keys: &str, target: Target, errors: &mut Vec<String>) {
+        match parse_sequence(keys) {
+            Ok(sequence) => {
+                if matches!(target, Target::Action(Action::None)) {
+                    self.bindings.remove(&sequence);
+                } else {
+                    self.bindings.insert(sequence, target);
+                }
+            }
+            Err(e) => errors.push(format!("Invalid keybinding \"{}\": {}", keys, e)),
+        }
+    }
+
+    /// Returns the binding for exactly `pending` and whether a longer binding starts with it
+    pub fn lookup(&self, pending: &[KeyChord]) -> (Option<&Target>, bool) {
+        let mut exact = None;
+        let mut is_prefix = false;
+        for (binding, target) in &self.bindings {
+            match matches_keybinding(pending, binding) {
+                KeyMatch::Exact => exact = Some(target),
+                KeyMatch::Prefix => is_prefix = true,
+                KeyMatch::None => {}
+            }
+        }
+        (exact, is_prefix)
+    }
+}
@@ -1,41 +1,113 @@
 mod app;
+mod archive;
+mod bookmarks;
+mod cli;
 mod clipboard;
 mod config;
 mod events;
 mod file_ops;
+mod finder;
+mod frecency;
 mod fuzzy;
+mod grep;
+mod jobs;
+mod keymap;
+mod opener;
+mod preview;
+mod rename;
+mod shell;
+mod trash;
 mod ui;
+mod walker;
+mod watcher;
 
 use anyhow::Result;
-use app::App;
+use app::{App, External};
+use cli::Command;
 use config::Config;
 use crossterm::{
     event::{self, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use directories::BaseDirs;
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
 use tokio::time::{interval, Duration};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::Query(query)) => return print_query(&query),
+        Ok(Command::Init(shell)) => match shell::init_script(&shell) {
+            Some(script) => {
+                print!("{}", script);
+                return Ok(());
+            }
+            None => anyhow::bail!("unsupported shell '{}' (expected one of: {})", shell, shell::SHELLS),
+        },
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Ok(Command::Version) => {
+            println!("jumper {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("Error: {}\n\nFor more information, try '--help'.", e);
+            std::process::exit(2);
+        }
+    };
+
     // Load configuration
-    let config = Config::load().unwrap_or_else(|_| {
-        eprintln!("Warning: Could not load config, using defaults");
-        Config::default()
-    });
+    let config = match &args.config {
+        Some(path) => Config::load_from(path)
+            .map_err(|e| anyhow::anyhow!("Could not load config {}: {}", path.display(), e))?,
+        None => Config::load().unwrap_or_else(|_| {
+            eprintln!("Warning: Could not load config, using defaults");
+            Config::default()
+        }),
+    };
+
+    // A file argument opens its directory with the file selected
+    let (start_dir, select) = match &args.path {
+        Some(path) => {
+            let path = path
+                .canonicalize()
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            if path.is_dir() {
+                (path, None)
+            } else {
+                let parent = path.parent().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("/"));
+                (parent, Some(path))
+            }
+        }
+        None => (std::env::current_dir()?, None),
+    };
+
+    // Draw on stderr when stdout is captured, e.g. `cd "$(jumper --print-last-dir)"`
+    let mut output: Box<dyn Write> = if io::stdout().is_terminal() {
+        Box::new(io::stdout())
+    } else {
+        Box::new(io::stderr())
+    };
 
     // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    setup_terminal(&mut output)?;
+    let backend = CrosstermBackend::new(output);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
-    let mut app = App::new(config)?;
+    let mut app = App::new(config, start_dir)?;
+    app.choose_files = args.choose_files.is_some();
+    if let Some(path) = select {
+        app.select_path(&path);
+    }
 
     // Start directory size calculations
     app.start_dir_size_calculation();
@@ -44,37 +116,112 @@ async fn main() -> Result<()> {
     let result = run_app(&mut terminal, &mut app).await;
 
     // Restore terminal
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+    restore_terminal(&mut terminal)?;
 
     if let Err(err) = result {
         eprintln!("Error: {}", err);
     }
 
-    // Write current directory to temp file for shell integration
-    if let Some(base_dirs) = BaseDirs::new() {
-        let jumper_cache = base_dirs.cache_dir().join("jumper");
-        if let Err(e) = std::fs::create_dir_all(&jumper_cache) {
-            eprintln!("Warning: Could not create cache directory: {}", e);
-        } else {
-            let last_dir_file = jumper_cache.join("lastdir");
-            if let Err(e) = std::fs::write(&last_dir_file, app.current_dir.to_string_lossy().as_bytes()) {
-                eprintln!("Warning: Could not write lastdir file: {}", e);
+    if let Err(e) = app.frecency.save() {
+        eprintln!("Warning: Could not save directory history: {}", e);
+    }
+
+    if let Some(choose_files) = &args.choose_files {
+        if !app.chosen_files.is_empty() {
+            let mut content = String::new();
+            for path in &app.chosen_files {
+                content.push_str(&path.to_string_lossy());
+                content.push('\n');
+            }
+            if let Err(e) = std::fs::write(choose_files, content) {
+                eprintln!("Warning: Could not write {}: {}", choose_files.display(), e);
             }
         }
     }
 
+    if let Some(choose_dir) = &args.choose_dir {
+        if let Err(e) = std::fs::write(choose_dir, app.tab.current_dir.to_string_lossy().as_bytes()) {
+            eprintln!("Warning: Could not write {}: {}", choose_dir.display(), e);
+        }
+    }
+
+    if args.print_last_dir {
+        println!("{}", app.tab.current_dir.display());
+    }
+
+    // The shell wrapper only changes directory after the quit-and-cd key
+    if let Some(cwd_file) = &args.cwd_file {
+        if app.cd_on_quit {
+            if let Err(e) = std::fs::write(cwd_file, app.tab.current_dir.to_string_lossy().as_bytes()) {
+                eprintln!("Warning: Could not write {}: {}", cwd_file.display(), e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn setup_terminal<W: Write>(output: &mut W) -> Result<()> {
+    enable_raw_mode()?;
+    execute!(output, EnterAlternateScreen)?;
     Ok(())
 }
 
-async fn run_app<B: ratatui::backend::Backend>(
+fn restore_terminal<B: Backend + Write>(terminal: &mut Terminal<B>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+/// Hands the terminal to an external program, then restores the TUI and reloads
+/// the listing since the program may have changed files
+fn run_external<B: Backend + Write>(terminal: &mut Terminal<B>, app: &mut App, external: External) -> Result<()> {
+    restore_terminal(terminal)?;
+    let result = match &external {
+        External::Edit(paths) => file_ops::edit_files(paths),
+        External::Open(opener, path) => opener::run_in_terminal(opener, path),
+        External::BulkRename { file, .. } => file_ops::edit_files(std::slice::from_ref(file)),
+    };
+    setup_terminal(terminal.backend_mut())?;
+    terminal.clear()?;
+
+    if let Err(e) = result {
+        app.error_message = Some(format!("{}", e));
+    } else if let External::BulkRename { file, originals } = &external {
+        app.finish_bulk_rename(file, originals.clone());
+    }
+    if let External::BulkRename { file, .. } = &external {
+        let _ = std::fs::remove_file(file);
+    }
+    if let Err(e) = app.reload_directory().and_then(|_| app.reload_other_pane()) {
+        app.error_message = Some(format!("Error reloading directory: {}", e));
+    }
+    app.start_dir_size_calculation();
+    Ok(())
+}
+
+fn print_query(query: &str) -> Result<()> {
+    let frecency = frecency::Frecency::load()?;
+    let current_dir = std::env::current_dir().ok();
+    match frecency.query(query, current_dir.as_deref()).first() {
+        Some(found) => {
+            println!("{}", found.path.display());
+            Ok(())
+        }
+        None => anyhow::bail!("no match found for '{}'", query),
+    }
+}
+
+async fn run_app<B: Backend + Write>(
     terminal: &mut Terminal<B>,
     app: &mut App,
 ) -> Result<()> {
     let mut render_interval = interval(Duration::from_millis(16)); // ~60 FPS
     let mut flash_timer: Option<tokio::time::Instant> = None;
     let mut error_timer: Option<tokio::time::Instant> = None;
+    let mut status_timer: Option<tokio::time::Instant> = None;
+    let mut key_timer: Option<tokio::time::Instant> = None;
 
     loop {
         // Clear flash copied paths after timeout
@@ -93,6 +240,22 @@ async fn run_app<B: ratatui::backend::Backend>(
             }
         }
 
+        // Clear status message after 3 seconds
+        if let Some(timer) = status_timer {
+            if timer.elapsed() >= Duration::from_millis(3000) {
+                app.status_message = None;
+                status_timer = None;
+            }
+        }
+
+        // Give up waiting for the rest of a key sequence and run what was typed (y without yy)
+        if let Some(timer) = key_timer {
+            if timer.elapsed() >= Duration::from_millis(app.config.behavior.key_timeout_ms) {
+                events::run_pending_keys(app)?;
+                key_timer = None;
+            }
+        }
+
         // Set timer when flash copied paths is shown
         if !app.flash_copied_paths.is_empty() && flash_timer.is_none() {
             flash_timer = Some(tokio::time::Instant::now());
@@ -105,23 +268,55 @@ async fn run_app<B: ratatui::backend::Backend>(
             error_timer = None;
         }
 
+        // Set timer when status message is shown
+        if app.status_message.is_some() && status_timer.is_none() {
+            status_timer = Some(tokio::time::Instant::now());
+        } else if app.status_message.is_none() {
+            status_timer = None;
+        }
+
+        // Set timer when a key sequence is partly typed; every key press restarts it
+        if !app.pending_keys.is_empty() && key_timer.is_none() {
+            key_timer = Some(tokio::time::Instant::now());
+        } else if app.pending_keys.is_empty() {
+            key_timer = None;
+        }
+
         // Check for directory size updates
         app.check_dir_size_updates();
 
+        // Check for background job progress
+        app.check_job_updates();
+
+        // Rank newly found files in the finder
+        app.check_finder_updates();
+
+        // Collect content search results
+        app.check_grep_updates();
+
+        // Reload listings that changed on disk
+        app.check_watcher();
+
+        // Load the preview for the entry under the cursor
+        app.update_preview();
+
         // Draw UI
         terminal.draw(|f| ui::render_ui(f, app))?;
 
-        // Handle events
+        // Wait for the next render tick, or a little less
         tokio::select! {
-            _ = render_interval.tick() => {
-                // Render tick - UI already drawn above
+            _ = render_interval.tick() => {}
+            _ = tokio::time::sleep(Duration::from_millis(10)) => {}
+        }
+
+        // Handle events after either wait, so frames slower than a tick cannot starve input
+        if event::poll(Duration::from_millis(0))? {
+            if let Event::Key(key) = event::read()? {
+                events::handle_key_event(app, key).await?;
+                key_timer = None;
             }
-            _ = tokio::time::sleep(Duration::from_millis(10)) => {
-                if event::poll(Duration::from_millis(0))? {
-                    if let Event::Key(key) = event::read()? {
-                        events::handle_key_event(app, key).await?;
-                    }
-                }
+            if let Some(external) = app.external.take() {
+                run_external(terminal, app, external)?;
             }
         }
 
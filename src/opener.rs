@@ -0,0 +1,213 @@
+use anyhow::Result;
+use globset::Glob;
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const SNIFF_BYTES: u64 = 8 * 1024;
+
+/// A command for opening files, from one entry of the `[openers]` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Opener {
+    pub pattern: String, // the entry's key: a MIME type, a glob on the file name or an extension
+    pub name: String,    // label in the open-with menu; the command is shown when empty
+    pub command: String, // `{}` is replaced by the path; appended when missing
+    pub terminal: bool,  // runs in the foreground with jumper suspended; detached otherwise
+}
+
+// The value of an `[openers]` entry: one command or a list of them
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum OpenerCommands {
+    One(OpenerCommand),
+    Many(Vec<OpenerCommand>),
+}
+
+// A bare string is a detached command without a name
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum OpenerCommand {
+    Plain(String),
+    Full {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        name: String,
+        command: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        terminal: bool,
+    },
+}
+
+impl Opener {
+    pub fn label(&self) -> &str {
+        if self.name.is_empty() {
+            &self.command
+        } else {
+            &self.name
+        }
+    }
+
+    /// A pattern with a `/` is a MIME type (`image/*`, `application/pdf`), one with `*`, `?`,
+    /// `[` or `{` is a glob on the file name, and anything else is an extension (`rs` or `.rs`).
+    /// `mime_types` are the types of `path`, from `mime_types`
+    pub fn matches(&self, path: &Path, mime_types: &[String]) -> bool {
+        let pattern = self.pattern.as_str();
+        if pattern.contains('/') {
+            mime_types.iter().any(|mime| mime_matches(pattern, mime))
+        } else if pattern.contains(['*', '?', '[', '{']) {
+            let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            Glob::new(pattern)
+                .map(|glob| glob.compile_matcher().is_match(name.as_ref()))
+                .unwrap_or(false)
+        } else {
+            let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+            extension.is_some_and(|extension| pattern.trim_start_matches('.').to_lowercase() == extension)
+        }
+    }
+
+    // Run through `sh -c` so commands may use pipes and arguments freely
+    fn shell_command(&self, path: &Path) -> Command {
+        let quoted = shell_quote(&path.to_string_lossy());
+        let line = if self.command.contains("{}") {
+            self.command.replace("{}", &quoted)
+        } else {
+            format!("{} {}", self.command, quoted)
+        };
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(line);
+        command
+    }
+}
+
+/// (De)serializes the `[openers]` table, keeping its entries in the order they are written
+pub mod table {
+    use super::*;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Opener>, D::Error> {
+        struct TableVisitor;
+
+        impl<'de> Visitor<'de> for TableVisitor {
+            type Value = Vec<Opener>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a table of file patterns and the commands that open them")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut openers = Vec::new();
+                while let Some((pattern, commands)) = map.next_entry::<String, OpenerCommands>()? {
+                    let commands = match commands {
+                        OpenerCommands::One(command) => vec![command],
+                        OpenerCommands::Many(commands) => commands,
+                    };
+                    openers.extend(commands.into_iter().map(|command| match command {
+                        OpenerCommand::Plain(command) => Opener {
+                            pattern: pattern.clone(),
+                            name: String::new(),
+                            command,
+                            terminal: false,
+                        },
+                        OpenerCommand::Full { name, command, terminal } => Opener {
+                            pattern: pattern.clone(),
+                            name,
+                            command,
+                            terminal,
+                        },
+                    }));
+                }
+                Ok(openers)
+            }
+        }
+
+        deserializer.deserialize_map(TableVisitor)
+    }
+
+    pub fn serialize<S: Serializer>(openers: &[Opener], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for group in openers.chunk_by(|a, b| a.pattern == b.pattern) {
+            let commands: Vec<OpenerCommand> = group
+                .iter()
+                .map(|opener| OpenerCommand::Full {
+                    name: opener.name.clone(),
+                    command: opener.command.clone(),
+                    terminal: opener.terminal,
+                })
+                .collect();
+            map.serialize_entry(&group[0].pattern, &OpenerCommands::Many(commands))?;
+        }
+        map.end()
+    }
+}
+
+/// Openers from `openers` that apply to `path`, in config order
+pub fn matching<'a>(openers: &'a [Opener], path: &Path) -> Vec<&'a Opener> {
+    // Only sniffed when some opener asks for a MIME type
+    let mime_types = if openers.iter().any(|opener| opener.pattern.contains('/')) {
+        mime_types(path)
+    } else {
+        Vec::new()
+    };
+    openers.iter().filter(|opener| opener.matches(path, &mime_types)).collect()
+}
+
+/// Starts a GUI-style opener in its own process group, without waiting for it
+pub fn spawn_detached(opener: &Opener, path: &Path) -> Result<()> {
+    let mut child = opener
+        .shell_command(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    // Reap the process when it exits so it does not linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Runs a terminal opener and waits for it; the TUI has to be suspended first
+pub fn run_in_terminal(opener: &Opener, path: &Path) -> Result<()> {
+    let status = opener.shell_command(path).status()?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", opener.label(), status);
+    }
+    Ok(())
+}
+
+// Guessed from the extension; files without a known one are text/plain unless binary.
+// Anything but a regular file is never read, as FIFOs and devices may block.
+fn mime_types(path: &Path) -> Vec<String> {
+    if !fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+        return vec!["application/octet-stream".to_string()];
+    }
+
+    let guessed: Vec<String> = mime_guess::from_path(path).iter().map(|m| m.essence_str().to_string()).collect();
+    if !guessed.is_empty() {
+        return guessed;
+    }
+
+    let mut head = Vec::new();
+    let readable = fs::File::open(path)
+        .and_then(|file| file.take(SNIFF_BYTES).read_to_end(&mut head))
+        .is_ok();
+    if readable && !crate::preview::is_binary(&head) {
+        vec!["text/plain".to_string()]
+    } else {
+        vec!["application/octet-stream".to_string()]
+    }
+}
+
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(kind) => mime.split('/').next() == Some(kind),
+        None => pattern == mime,
+    }
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
@@ -0,0 +1,232 @@
+use crate::app::{read_entries, sort_entries, FileEntry};
+use crate::archive::Archive;
+use crate::config::SortMode;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, ThemeSet};
+use syntect::parsing::SyntaxSet;
+
+// How much of a file is read for previewing
+const MAX_LINES: usize = 200;
+const SNIFF_BYTES: usize = 8 * 1024;
+const HEX_BYTES: usize = 256;
+const TEXT_BYTES: usize = 64 * 1024; // also caps files without line breaks
+
+pub enum Preview {
+    Text(Vec<Line<'static>>),
+    Directory(Vec<FileEntry>),
+    Binary(Vec<Line<'static>>),
+    Error(String),
+}
+
+/// Builds the preview for `path`; this does blocking IO and is meant to run off the UI thread
+pub fn load_preview(path: &Path, show_hidden: bool, sort_mode: &SortMode, ascending: bool) -> Preview {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return Preview::Error(e.to_string()),
+    };
+
+    if metadata.is_dir() {
+        return match read_entries(path, show_hidden) {
+            Ok(entries) => Preview::Directory(sort_entries(entries, sort_mode, ascending)),
+            Err(e) => Preview::Error(e.to_string()),
+        };
+    }
+
+    // FIFOs, sockets and devices may block or never end, so they are described, not read
+    if !metadata.is_file() {
+        let mut lines = vec![Line::from(vec![
+            Span::styled("Type:        ", Style::default().fg(Color::DarkGray)),
+            Span::raw(special_kind(&metadata)),
+        ])];
+        lines.extend(binary_summary(metadata.len(), metadata.modified().ok(), metadata.permissions().mode(), &[]));
+        return Preview::Binary(lines);
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    if let Err(e) = fs::File::open(path).and_then(|f| f.take(SNIFF_BYTES as u64).read_to_end(&mut head)) {
+        return Preview::Error(e.to_string());
+    }
+
+    if is_binary(&head) {
+        let mode = metadata.permissions().mode();
+        return Preview::Binary(binary_summary(metadata.len(), metadata.modified().ok(), mode, &head));
+    }
+
+    match read_head_lines(path) {
+        Ok(text) => Preview::Text(highlight(path, &text)),
+        Err(e) => Preview::Error(e.to_string()),
+    }
+}
+
+/// Builds the preview for `path` inside `archive`, reading from the archive
+pub fn load_archive_preview(archive: &Archive, path: &Path, show_hidden: bool, sort_mode: &SortMode, ascending: bool) -> Preview {
+    if archive.is_dir(path) {
+        return match archive.read_dir(path, show_hidden) {
+            Ok(entries) => Preview::Directory(sort_entries(entries, sort_mode, ascending)),
+            Err(e) => Preview::Error(e.to_string()),
+        };
+    }
+
+    let Some(entry) = archive.entry(path) else {
+        return Preview::Error(String::from("Not found in the archive"));
+    };
+    let head = match archive.read_head(path, TEXT_BYTES) {
+        Ok(head) => head,
+        Err(e) => return Preview::Error(e.to_string()),
+    };
+    let sniffed = &head[..head.len().min(SNIFF_BYTES)];
+    if is_binary(sniffed) {
+        return Preview::Binary(binary_summary(entry.size, entry.modified, entry.mode, sniffed));
+    }
+
+    let text: String = String::from_utf8_lossy(&head)
+        .split_inclusive('\n')
+        .take(MAX_LINES)
+        .collect();
+    Preview::Text(highlight(path, &text))
+}
+
+/// Heuristic used for previews and content search: NUL bytes or invalid UTF-8
+pub fn is_binary(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return true;
+    }
+    // A multi-byte character may be cut at the end of the sniffed chunk
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
+fn special_kind(metadata: &fs::Metadata) -> &'static str {
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = metadata.file_type();
+    if file_type.is_fifo() {
+        "named pipe"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_char_device() {
+        "character device"
+    } else if file_type.is_block_device() {
+        "block device"
+    } else {
+        "special file"
+    }
+}
+
+fn read_head_lines(path: &Path) -> std::io::Result<String> {
+    use std::io::BufRead;
+
+    let mut reader = std::io::BufReader::new(fs::File::open(path)?.take(TEXT_BYTES as u64));
+    let mut text = String::new();
+    let mut line = Vec::new();
+    for _ in 0..MAX_LINES {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        text.push_str(&String::from_utf8_lossy(&line));
+    }
+    Ok(text)
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+fn highlight(path: &Path, text: &str) -> Vec<Line<'static>> {
+    let syntaxes = syntax_set();
+    let syntax = syntaxes
+        .find_syntax_for_file(path)
+        .ok()
+        .flatten()
+        .or_else(|| syntaxes.find_syntax_by_first_line(text))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, &theme_set().themes["base16-ocean.dark"]);
+
+    let mut lines = Vec::new();
+    for line in syntect::util::LinesWithEndings::from(text) {
+        let Ok(regions) = highlighter.highlight_line(line, syntaxes) else {
+            lines.push(Line::from(line.trim_end_matches('\n').replace('\t', "    ")));
+            continue;
+        };
+
+        let spans: Vec<Span<'static>> = regions
+            .into_iter()
+            .map(|(style, piece)| {
+                let mut span_style = Style::default().fg(Color::Rgb(
+                    style.foreground.r,
+                    style.foreground.g,
+                    style.foreground.b,
+                ));
+                if style.font_style.contains(FontStyle::BOLD) {
+                    span_style = span_style.add_modifier(Modifier::BOLD);
+                }
+                if style.font_style.contains(FontStyle::ITALIC) {
+                    span_style = span_style.add_modifier(Modifier::ITALIC);
+                }
+                let content = piece.trim_end_matches('\n').replace('\t', "    ");
+                Span::styled(content, span_style)
+            })
+            .collect();
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+fn binary_summary(size: u64, modified: Option<SystemTime>, mode: u32, head: &[u8]) -> Vec<Line<'static>> {
+    let label = Style::default().fg(Color::DarkGray);
+    let modified = modified
+        .map(|time| {
+            chrono::DateTime::<chrono::Local>::from(time)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| String::from("unknown"));
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Size:        ", label),
+            Span::raw(format!("{} bytes", size)),
+        ]),
+        Line::from(vec![
+            Span::styled("Modified:    ", label),
+            Span::raw(modified),
+        ]),
+        Line::from(vec![
+            Span::styled("Permissions: ", label),
+            Span::raw(format!("{:o}", mode & 0o7777)),
+        ]),
+        Line::from(""),
+    ];
+
+    // Classic hex dump: offset, 16 bytes in hex, printable ASCII
+    for (row, chunk) in head.chunks(16).take(HEX_BYTES / 16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:08x}  ", row * 16), label),
+            Span::raw(format!("{:<48} ", hex.join(" "))),
+            Span::styled(ascii, label),
+        ]));
+    }
+    lines
+}
@@ -0,0 +1,481 @@
+use anyhow::Result;
+use ratatui::widgets::ListState;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Renames of several entries, applied together so names can be swapped or rotated
+#[derive(Debug, Clone)]
+pub struct RenamePlan {
+    pub renames: Vec<(PathBuf, PathBuf)>, // (current path, new path); unchanged entries are left out
+}
+
+impl RenamePlan {
+    /// Pairs each of `originals` with the name at the same position in `new_names`,
+    /// refusing invalid names, duplicates and clashes with entries that are not renamed
+    pub fn new(originals: &[PathBuf], new_names: &[String]) -> Result<Self> {
+        if originals.len() != new_names.len() {
+            anyhow::bail!(
+                "expected {} names but got {}; lines must not be added or removed",
+                originals.len(),
+                new_names.len()
+            );
+        }
+
+        let sources: HashSet<&Path> = originals.iter().map(|p| p.as_path()).collect();
+        let mut targets = HashSet::new();
+        let mut renames = Vec::new();
+        for (original, name) in originals.iter().zip(new_names) {
+            validate_name(name)?;
+            // Names are edited as text, so one that is not valid UTF-8 comes back lossily
+            // decoded; left as it was, it must not be renamed to that decoded form
+            let unchanged = original.file_name().is_some_and(|n| n.to_string_lossy() == name.as_str());
+            let target = if unchanged { original.clone() } else { original.with_file_name(name) };
+            if !targets.insert(target.clone()) {
+                anyhow::bail!("{} is used more than once", name);
+            }
+            if target == *original {
+                continue;
+            }
+            // Only entries that are renamed away themselves may be replaced
+            if target.symlink_metadata().is_ok() && !sources.contains(target.as_path()) {
+                anyhow::bail!("{} already exists", name);
+            }
+            renames.push((original.clone(), target));
+        }
+
+        Ok(Self { renames })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+    }
+
+    /// The plan that undoes this one
+    pub fn inverse(&self) -> Self {
+        Self {
+            renames: self.renames.iter().map(|(from, to)| (to.clone(), from.clone())).collect(),
+        }
+    }
+
+    /// Moves every source to a temporary name first, then each temporary name to its
+    /// target, so a → b, b → a works. On failure everything done so far is rolled back.
+    pub fn apply(&self) -> Result<()> {
+        let mut staged: Vec<(&Path, PathBuf)> = Vec::new();
+        for (index, (from, _)) in self.renames.iter().enumerate() {
+            let temp = temp_name(from, index);
+            if let Err(e) = fs::rename(from, &temp) {
+                rollback(&staged, &[]);
+                anyhow::bail!("Failed to rename {}: {}", from.display(), e);
+            }
+            staged.push((from, temp));
+        }
+
+        let mut finished: Vec<(&Path, &Path)> = Vec::new();
+        for ((_, to), (_, temp)) in self.renames.iter().zip(&staged) {
+            let result = if to.symlink_metadata().is_ok() {
+                Err(anyhow::anyhow!("{} already exists", to.display()))
+            } else {
+                fs::rename(temp, to).map_err(|e| anyhow::anyhow!("Failed to rename to {}: {}", to.display(), e))
+            };
+            if let Err(e) = result {
+                rollback(&staged, &finished);
+                return Err(e);
+            }
+            finished.push((temp, to));
+        }
+        Ok(())
+    }
+}
+
+/// Renames computed from a regex or a template as it is typed, for the live preview
+pub struct PatternRename {
+    pub originals: Vec<PathBuf>,
+    pub find: String,    // regex; when empty, `replace` is a template for the whole name
+    pub replace: String,
+    pub editing_replace: bool,
+    pub new_names: Vec<String>,
+    pub conflicts: Vec<bool>, // invalid, used twice or taken by an entry that is not renamed
+    pub error: Option<String>,
+    pub list_state: ListState,
+    existing: HashSet<String>, // names listed in the directory
+}
+
+impl PatternRename {
+    pub fn new(originals: Vec<PathBuf>, existing: HashSet<String>) -> Self {
+        let mut pattern = Self {
+            originals,
+            find: String::new(),
+            replace: String::new(),
+            editing_replace: false,
+            new_names: Vec::new(),
+            conflicts: Vec::new(),
+            error: None,
+            list_state: ListState::default(),
+            existing,
+        };
+        pattern.list_state.select(Some(0));
+        pattern.update();
+        pattern
+    }
+
+    /// Recomputes the new names and their conflicts after the pattern changed
+    pub fn update(&mut self) {
+        let names: Vec<String> = self.originals.iter().map(|path| file_name(path)).collect();
+        let new_names = if self.find.is_empty() && self.replace.is_empty() {
+            Ok(names.clone())
+        } else {
+            pattern_names(&names, &self.find, &self.replace)
+        };
+        match new_names {
+            Ok(new_names) => {
+                self.new_names = new_names;
+                self.error = None;
+            }
+            Err(e) => {
+                // Keep showing the last valid preview while the pattern is incomplete
+                self.error = Some(e.to_string());
+                return;
+            }
+        }
+
+        let mut uses: HashMap<&str, usize> = HashMap::new();
+        for name in &self.new_names {
+            *uses.entry(name.as_str()).or_default() += 1;
+        }
+        let sources: HashSet<&str> = names.iter().map(String::as_str).collect();
+        self.conflicts = self.new_names
+            .iter()
+            .zip(&names)
+            .map(|(new, old)| {
+                validate_name(new).is_err()
+                    || uses[new.as_str()] > 1
+                    || (new != old && self.existing.contains(new) && !sources.contains(new.as_str()))
+            })
+            .collect();
+    }
+
+    pub fn conflict_count(&self) -> usize {
+        self.conflicts.iter().filter(|&&conflict| conflict).count()
+    }
+
+    pub fn changed_count(&self) -> usize {
+        self.originals
+            .iter()
+            .zip(&self.new_names)
+            .filter(|(path, new)| file_name(path) != **new)
+            .count()
+    }
+
+    /// The renames to apply; the names are checked again against the file system
+    pub fn plan(&self) -> Result<RenamePlan> {
+        if let Some(error) = &self.error {
+            anyhow::bail!("{}", error);
+        }
+        RenamePlan::new(&self.originals, &self.new_names)
+    }
+}
+
+/// New names from a regex find/replace, or from a template when `find` is empty.
+/// Templates may use {name}, {stem}, {ext} and a counter {n} (padded with {n:03});
+/// with a regex they go into the replacement next to $1-style groups.
+pub fn pattern_names(names: &[String], find: &str, replace: &str) -> Result<Vec<String>> {
+    let regex = if find.is_empty() {
+        None
+    } else {
+        Some(Regex::new(find).map_err(|_| anyhow::anyhow!("invalid regex"))?)
+    };
+
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let replacement = expand_template(replace, name, index + 1, regex.is_some())?;
+            Ok(match &regex {
+                Some(regex) => regex.replace_all(name, replacement.as_str()).into_owned(),
+                None => replacement,
+            })
+        })
+        .collect()
+}
+
+// Fills in the placeholders of `template` for one entry. For a regex replacement, $-groups are
+// kept as they are and `$` in the filled-in values is escaped.
+fn expand_template(template: &str, name: &str, number: usize, for_regex: bool) -> Result<String> {
+    let path = Path::new(name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '$' if for_regex => {
+                out.push(c);
+                if chars.peek() == Some(&'{') {
+                    // ${group}
+                    for c in chars.by_ref() {
+                        out.push(c);
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                } else if let Some(c) = chars.next() {
+                    out.push(c);
+                }
+            }
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => anyhow::bail!("unclosed {{"),
+                    }
+                }
+                let value = match placeholder.split_once(':') {
+                    None if placeholder == "name" => name.to_string(),
+                    None if placeholder == "stem" => stem.clone(),
+                    None if placeholder == "ext" => {
+                        // `.{ext}` leaves no trailing dot on names without an extension
+                        if ext.is_empty() && out.ends_with('.') {
+                            out.pop();
+                        }
+                        ext.clone()
+                    }
+                    None if placeholder == "n" => number.to_string(),
+                    Some(("n", width)) => match width.parse::<usize>() {
+                        Ok(w) if width.starts_with('0') => format!("{:0w$}", number, w = w),
+                        Ok(w) => format!("{:w$}", number, w = w),
+                        Err(_) => anyhow::bail!("invalid width in {{{}}}", placeholder),
+                    },
+                    _ => anyhow::bail!("unknown placeholder {{{}}}", placeholder),
+                };
+                if for_regex {
+                    out.push_str(&value.replace('$', "$$"));
+                } else {
+                    out.push_str(&value);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        anyhow::bail!("names must not be empty");
+    }
+    if name == "." || name == ".." || name.contains('/') || name.contains('\0') {
+        anyhow::bail!("invalid name: {}", name);
+    }
+    Ok(())
+}
+
+// A name next to `path` that nothing else is using
+fn temp_name(path: &Path, index: usize) -> PathBuf {
+    let mut attempt = 0;
+    loop {
+        let candidate = path.with_file_name(format!(".jumper-rename-{}-{}-{}", std::process::id(), index, attempt));
+        if candidate.symlink_metadata().is_err() {
+            return candidate;
+        }
+        attempt += 1;
+    }
+}
+
+// Best effort: put finished entries back on their temporary names, then restore the originals
+fn rollback(staged: &[(&Path, PathBuf)], finished: &[(&Path, &Path)]) {
+    for (temp, to) in finished.iter().rev() {
+        let _ = fs::rename(to, temp);
+    }
+    for (from, temp) in staged.iter().rev() {
+        let _ = fs::rename(temp, from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the system temp dir, removed again on drop
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("jumper-rename-test-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        // Creates a file whose content is its original name
+        fn file(&self, name: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, name).unwrap();
+            path
+        }
+
+        fn content(&self, name: &str) -> String {
+            fs::read_to_string(self.0.join(name)).unwrap()
+        }
+
+        fn names(&self) -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn apply_swaps_two_names() {
+        let dir = TestDir::new("swap");
+        let originals = vec![dir.file("a"), dir.file("b")];
+
+        let plan = RenamePlan::new(&originals, &strings(&["b", "a"])).unwrap();
+        plan.apply().unwrap();
+
+        assert_eq!(dir.names(), ["a", "b"]);
+        assert_eq!(dir.content("a"), "b");
+        assert_eq!(dir.content("b"), "a");
+    }
+
+    #[test]
+    fn apply_rotates_names_and_inverse_undoes_it() {
+        let dir = TestDir::new("rotate");
+        let originals = vec![dir.file("a"), dir.file("b"), dir.file("c")];
+
+        let plan = RenamePlan::new(&originals, &strings(&["b", "c", "a"])).unwrap();
+        plan.apply().unwrap();
+        assert_eq!(dir.names(), ["a", "b", "c"]);
+        assert_eq!(dir.content("b"), "a");
+        assert_eq!(dir.content("c"), "b");
+        assert_eq!(dir.content("a"), "c");
+
+        plan.inverse().apply().unwrap();
+        for name in ["a", "b", "c"] {
+            assert_eq!(dir.content(name), name);
+        }
+    }
+
+    #[test]
+    fn unchanged_entries_are_left_out() {
+        let dir = TestDir::new("unchanged");
+        let originals = vec![dir.file("a"), dir.file("b")];
+
+        let plan = RenamePlan::new(&originals, &strings(&["a", "c"])).unwrap();
+        assert_eq!(plan.renames, vec![(dir.0.join("b"), dir.0.join("c"))]);
+    }
+
+    #[test]
+    fn new_refuses_duplicates_and_existing_names() {
+        let dir = TestDir::new("refuse");
+        let originals = vec![dir.file("a"), dir.file("b")];
+        dir.file("taken");
+
+        assert!(RenamePlan::new(&originals, &strings(&["c", "c"])).is_err());
+        assert!(RenamePlan::new(&originals, &strings(&["taken", "b"])).is_err());
+        assert!(RenamePlan::new(&originals, &strings(&["a/b", "b"])).is_err());
+        assert!(RenamePlan::new(&originals, &strings(&["a"])).is_err());
+    }
+
+    #[test]
+    fn apply_rolls_back_when_a_target_appears() {
+        let dir = TestDir::new("rollback-target");
+        let originals = vec![dir.file("a"), dir.file("b")];
+        let plan = RenamePlan::new(&originals, &strings(&["x", "y"])).unwrap();
+
+        // Created after planning, so only the second rename finds it
+        dir.file("y");
+        assert!(plan.apply().is_err());
+
+        assert_eq!(dir.names(), ["a", "b", "y"]);
+        assert_eq!(dir.content("a"), "a");
+        assert_eq!(dir.content("b"), "b");
+        assert_eq!(dir.content("y"), "y");
+    }
+
+    #[test]
+    fn apply_rolls_back_when_a_source_is_gone() {
+        let dir = TestDir::new("rollback-source");
+        let originals = vec![dir.file("a"), dir.file("b")];
+        let plan = RenamePlan::new(&originals, &strings(&["b", "a"])).unwrap();
+
+        fs::remove_file(dir.0.join("b")).unwrap();
+        assert!(plan.apply().is_err());
+
+        assert_eq!(dir.names(), ["a"]);
+        assert_eq!(dir.content("a"), "a");
+    }
+
+    #[test]
+    fn template_pads_the_counter() {
+        assert_eq!(expand_template("{stem}_{n:03}.{ext}", "a.jpg", 7, false).unwrap(), "a_007.jpg");
+        assert_eq!(expand_template("{n:3}", "a", 12, false).unwrap(), " 12");
+        assert_eq!(expand_template("{n}", "a", 12, false).unwrap(), "12");
+        assert!(expand_template("{n:x}", "a", 1, false).is_err());
+    }
+
+    #[test]
+    fn template_drops_the_dot_before_a_missing_extension() {
+        assert_eq!(expand_template("{stem}.{ext}", "README", 1, false).unwrap(), "README");
+        assert_eq!(expand_template("{stem}.{ext}", "notes.txt", 1, false).unwrap(), "notes.txt");
+        assert_eq!(expand_template("{stem}.bak.{ext}", "archive.tar.gz", 1, false).unwrap(), "archive.tar.bak.gz");
+    }
+
+    #[test]
+    fn template_braces_and_errors() {
+        assert_eq!(expand_template("{{{name}}}", "a", 1, false).unwrap(), "{a}");
+        assert!(expand_template("{size}", "a", 1, false).is_err());
+        assert!(expand_template("{name", "a", 1, false).is_err());
+    }
+
+    #[test]
+    fn regex_groups_are_kept_and_dollars_in_values_escaped() {
+        assert_eq!(expand_template("$1_{n}", "a", 2, true).unwrap(), "$1_2");
+        assert_eq!(expand_template("${x}{n}", "a", 2, true).unwrap(), "${x}2");
+        assert_eq!(expand_template("{name}", "a$1", 1, true).unwrap(), "a$$1");
+        // Without a regex, $ is an ordinary character
+        assert_eq!(expand_template("$1{n}", "a", 2, false).unwrap(), "$12");
+    }
+
+    #[test]
+    fn pattern_names_with_regex_and_template() {
+        let names = strings(&["IMG_1.jpg", "IMG_2.jpg"]);
+        assert_eq!(
+            pattern_names(&names, "^IMG_(\\d+)", "photo_${1}_{n:02}").unwrap(),
+            ["photo_1_01.jpg", "photo_2_02.jpg"]
+        );
+        assert_eq!(pattern_names(&names, "", "{n}.{ext}").unwrap(), ["1.jpg", "2.jpg"]);
+        // A $ in a file name is inserted literally, not read as a group
+        assert_eq!(pattern_names(&strings(&["a$1"]), "^(.*)$", "{name}-$1").unwrap(), ["a$1-a$1"]);
+        assert!(pattern_names(&names, "(", "x").is_err());
+    }
+}
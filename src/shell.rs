@@ -0,0 +1,16 @@
+// Wrappers printed by `jumper init <shell>`; the same files are shipped in shell/
+const POSIX: &str = include_str!("../shell/jumper.sh");
+const FISH: &str = include_str!("../shell/jumper.fish");
+const NUSHELL: &str = include_str!("../shell/jumper.nu");
+
+pub const SHELLS: &str = "bash, zsh, fish, nushell";
+
+/// Returns the integration script for `shell`, or None if it is not supported
+pub fn init_script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" | "zsh" | "sh" => Some(POSIX),
+        "fish" => Some(FISH),
+        "nushell" | "nu" => Some(NUSHELL),
+        _ => None,
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use directories::BaseDirs;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::Write;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+// FreeDesktop.org trash implementation (home trash only):
+// items live in $XDG_DATA_HOME/Trash/files, metadata in $XDG_DATA_HOME/Trash/info
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashItem {
+    pub trashed_path: PathBuf,
+    pub info_path: PathBuf,
+    pub original_path: PathBuf,
+    pub deletion_date: String,
+    pub is_dir: bool,
+}
+
+pub fn trash_dir() -> Result<PathBuf> {
+    if let Some(base_dirs) = BaseDirs::new() {
+        Ok(base_dirs.data_dir().join("Trash"))
+    } else {
+        anyhow::bail!("Could not determine trash directory")
+    }
+}
+
+pub fn move_to_trash(path: &Path) -> Result<TrashItem> {
+    let trash = trash_dir()?;
+    let files_dir = trash.join("files");
+    let info_dir = trash.join("info");
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+
+    let original_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    let is_dir = original_path.is_dir();
+    let file_name = original_path
+        .file_name()
+        .context("Cannot trash a path without a file name")?
+        .to_os_string();
+    let deletion_date = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    // Reserve a unique name by atomically creating the .trashinfo file first
+    let (trash_name, mut info_file) = {
+        let mut counter = 1;
+        loop {
+            let mut candidate = file_name.clone();
+            if counter > 1 {
+                candidate.push(format!(".{}", counter));
+            }
+            let info_path = info_dir.join(with_suffix(&candidate, ".trashinfo"));
+            if !files_dir.join(&candidate).exists() {
+                match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                    Ok(file) => break (candidate, file),
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            counter += 1;
+        }
+    };
+
+    let info_path = info_dir.join(with_suffix(&trash_name, ".trashinfo"));
+    let trashed_path = files_dir.join(&trash_name);
+
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&original_path),
+        deletion_date
+    );
+    if let Err(e) = info_file
+        .write_all(info.as_bytes())
+        .map_err(anyhow::Error::from)
+        .and_then(|_| crate::file_ops::move_path(&original_path, &trashed_path))
+    {
+        let _ = fs::remove_file(&info_path);
+        return Err(e);
+    }
+
+    Ok(TrashItem {
+        trashed_path,
+        info_path,
+        original_path,
+        deletion_date,
+        is_dir,
+    })
+}
+
+pub fn list_items() -> Result<Vec<TrashItem>> {
+    let trash = trash_dir()?;
+    let files_dir = trash.join("files");
+    let info_dir = trash.join("info");
+
+    let mut items = Vec::new();
+    if !info_dir.exists() {
+        return Ok(items);
+    }
+
+    for entry in fs::read_dir(&info_dir)?.flatten() {
+        let info_path = entry.path();
+        if info_path.extension().and_then(|e| e.to_str()) != Some("trashinfo") {
+            continue;
+        }
+        let Some(trash_name) = info_path.file_stem().map(|s| s.to_os_string()) else {
+            continue;
+        };
+        let trashed_path = files_dir.join(&trash_name);
+        // Orphaned metadata without a file is skipped
+        let Ok(metadata) = fs::symlink_metadata(&trashed_path) else {
+            continue;
+        };
+        let Ok(content) = fs::read_to_string(&info_path) else {
+            continue;
+        };
+
+        let mut original_path = None;
+        let mut deletion_date = String::new();
+        for line in content.lines() {
+            if let Some(value) = line.strip_prefix("Path=") {
+                original_path = Some(decode_path(value));
+            } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                deletion_date = value.to_string();
+            }
+        }
+
+        if let Some(original_path) = original_path {
+            items.push(TrashItem {
+                trashed_path,
+                info_path,
+                original_path,
+                deletion_date,
+                is_dir: metadata.is_dir(),
+            });
+        }
+    }
+
+    // Most recently deleted first
+    items.sort_by(|a, b| b.deletion_date.cmp(&a.deletion_date));
+    Ok(items)
+}
+
+pub fn restore(item: &TrashItem) -> Result<()> {
+    if item.original_path.exists() {
+        anyhow::bail!("{} already exists", item.original_path.display());
+    }
+    if let Some(parent) = item.original_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    crate::file_ops::move_path(&item.trashed_path, &item.original_path)?;
+    fs::remove_file(&item.info_path)?;
+    Ok(())
+}
+
+pub fn purge(item: &TrashItem) -> Result<()> {
+    if item.is_dir {
+        fs::remove_dir_all(&item.trashed_path)?;
+    } else {
+        fs::remove_file(&item.trashed_path)?;
+    }
+    fs::remove_file(&item.info_path)?;
+    Ok(())
+}
+
+fn with_suffix(name: &OsStr, suffix: &str) -> OsString {
+    let mut name = name.to_os_string();
+    name.push(suffix);
+    name
+}
+
+// Paths in .trashinfo files are percent-encoded like URIs, byte by byte, so names that
+// are not valid UTF-8 survive the round trip
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn decode_path(value: &str) -> PathBuf {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    PathBuf::from(OsString::from_vec(decoded))
+}
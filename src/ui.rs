@@ -1,10 +1,15 @@
-use crate::app::{App, ClipboardOperation, FileEntry, Mode};
+use crate::app::{App, ClipboardOperation, FileEntry, Mode, Tab};
 use crate::config::SortMode;
+use crate::finder::Finder;
+use crate::grep::Grep;
+use crate::keymap::format_sequence;
+use crate::preview::Preview;
+use crate::rename::{PatternRename, RenamePlan};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, LineGauge, List, ListItem, Paragraph},
     Frame,
 };
 
@@ -15,23 +20,227 @@ pub fn render_ui(frame: &mut Frame, app: &App) {
         return;
     }
 
-    let chunks = Layout::default()
+    // The tab bar is only shown once there is more than one tab
+    let tab_bar_height = if app.tabs.len() > 1 { 1 } else { 0 };
+    let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(1),    // File list
-            Constraint::Length(1), // Footer
+            Constraint::Length(tab_bar_height), // Tab bar
+            Constraint::Min(1),                 // File list
+            Constraint::Length(1),              // Footer
         ])
         .split(frame.area());
+    if tab_bar_height > 0 {
+        render_tab_bar(frame, app, layout[0]);
+    }
+    let chunks = [layout[1], layout[2]];
 
-    render_file_list(frame, app, chunks[0]);
+    if matches!(app.mode, Mode::Trash | Mode::PurgeConfirm) {
+        render_trash(frame, app, chunks[0]);
+    } else if app.mode == Mode::Jobs {
+        render_jobs(frame, app, chunks[0]);
+    } else if let (Mode::Finder, Some(finder)) = (&app.mode, &app.finder) {
+        render_finder(frame, app, finder, chunks[0]);
+    } else if let (Mode::RenameConfirm, Some(plan)) = (&app.mode, &app.rename_plan) {
+        render_rename_plan(frame, app, plan, chunks[0]);
+    } else if let (Mode::PatternRename, Some(pattern)) = (&app.mode, &app.pattern_rename) {
+        render_pattern_rename(frame, app, pattern, chunks[0]);
+    } else if app.mode == Mode::OpenWith {
+        render_open_with(frame, app, chunks[0]);
+    } else if app.mode == Mode::Jump {
+        render_jump(frame, app, chunks[0]);
+    } else if let (Mode::Grep, Some(grep)) = (&app.mode, &app.grep) {
+        render_grep(frame, app, grep, chunks[0]);
+    } else if let Some(other) = &app.other_pane {
+        render_dual_pane(frame, app, other, chunks[0]);
+    } else if app.miller_columns {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20), // Parent
+                Constraint::Percentage(40), // Current
+                Constraint::Percentage(40), // Child
+            ])
+            .split(chunks[0]);
+        render_parent_column(frame, app, panes[0]);
+        render_file_list(frame, app, panes[1]);
+        render_preview(frame, app, panes[2]);
+    } else if app.show_preview {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[0]);
+        render_file_list(frame, app, panes[0]);
+        render_preview(frame, app, panes[1]);
+    } else {
+        render_file_list(frame, app, chunks[0]);
+    }
+    // The bookmarks list is drawn over the listing
+    if app.mode == Mode::Bookmarks {
+        render_bookmarks(frame, app, chunks[0]);
+    }
     render_footer(frame, app, chunks[1]);
 }
 
+// A `width` x `height` area in the middle of `area`, shrunk to fit
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn render_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let mut spans = Vec::new();
+    for (idx, title) in app.tab_titles().into_iter().enumerate() {
+        let style = if idx == app.active_tab {
+            Style::default()
+                .bg(app.config.colors.selected)
+                .fg(ratatui::style::Color::Black)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(ratatui::style::Color::DarkGray)
+        };
+        spans.push(Span::styled(format!(" {}:{} ", idx + 1, title), style));
+        spans.push(Span::raw(" "));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn render_parent_column(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::RIGHT)
+        .border_style(Style::default().fg(ratatui::style::Color::DarkGray));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let current_idx = app.tab.parent_files.iter().position(|f| f.path == app.tab.current_dir);
+    let items: Vec<ListItem> = app.tab.parent_files
+        .iter()
+        .map(|entry| {
+            let mut style = entry_style(entry, app);
+            // The directory we are in is highlighted like the cursor
+            if entry.path == app.tab.current_dir {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+            }
+            let name = if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            ListItem::new(Line::from(Span::styled(name, style)))
+        })
+        .collect();
+
+    let mut state = ratatui::widgets::ListState::default();
+    state.select(current_idx);
+    frame.render_stateful_widget(List::new(items), inner, &mut state);
+}
+
+fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::LEFT)
+        .border_style(Style::default().fg(ratatui::style::Color::DarkGray));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let dim_italic = Style::default()
+        .fg(ratatui::style::Color::DarkGray)
+        .add_modifier(Modifier::ITALIC);
+
+    let lines: Vec<Line> = match &app.preview {
+        None if app.preview_path.is_some() => vec![Line::from(Span::styled("loading...", dim_italic))],
+        None => Vec::new(),
+        Some((_, Preview::Text(lines))) | Some((_, Preview::Binary(lines))) => lines.clone(),
+        Some((_, Preview::Directory(entries))) if entries.is_empty() => {
+            vec![Line::from(Span::styled("empty", dim_italic))]
+        }
+        Some((_, Preview::Directory(entries))) => entries
+            .iter()
+            .take(inner.height as usize)
+            .map(|entry| {
+                let name = if entry.is_dir {
+                    format!("{}/", entry.name)
+                } else {
+                    entry.name.clone()
+                };
+                Line::from(Span::styled(name, entry_style(entry, app)))
+            })
+            .collect(),
+        Some((_, Preview::Error(error))) => vec![Line::from(Span::styled(error.clone(), dim_italic))],
+    };
+
+    frame.render_widget(Paragraph::new(lines), Rect { x: inner.x + 1, width: inner.width.saturating_sub(1), ..inner });
+}
+
+// Base colour and weight of an entry, without cursor/selection decorations
+fn entry_style(file: &FileEntry, app: &App) -> Style {
+    let color = if file.is_hidden {
+        app.config.colors.hidden
+    } else if file.is_dir {
+        app.config.colors.directory
+    } else if file.is_symlink {
+        app.config.colors.symlink
+    } else if file.is_executable {
+        app.config.colors.executable
+    } else {
+        app.config.colors.file
+    };
+
+    let style = Style::default().fg(color);
+    // Make directories bold
+    if file.is_dir {
+        style.add_modifier(Modifier::BOLD)
+    } else {
+        style
+    }
+}
+
 fn render_file_list(frame: &mut Frame, app: &App, area: Rect) {
-    let current_idx = app.list_state.selected();
+    render_tab_files(frame, app, &app.tab, true, area);
+}
+
+// Two panels side by side; the focused one is `app.tab`
+fn render_dual_pane(frame: &mut Frame, app: &App, other: &Tab, area: Rect) {
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let (focused_area, other_area) = if app.focus_left {
+        (panes[0], panes[1])
+    } else {
+        (panes[1], panes[0])
+    };
+
+    for (tab, pane_area, focused) in [(&app.tab, focused_area, true), (other, other_area, false)] {
+        let title_style = if focused {
+            Style::default().fg(app.config.colors.selected).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(ratatui::style::Color::DarkGray)
+        };
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().fg(ratatui::style::Color::DarkGray))
+            .title(Span::styled(display_dir(tab), title_style));
+        let inner = block.inner(pane_area);
+        frame.render_widget(block, pane_area);
+        render_tab_files(frame, app, tab, focused, inner);
+    }
+}
+
+fn render_tab_files(frame: &mut Frame, app: &App, tab: &Tab, focused: bool, area: Rect) {
+    // Only the focused list shows the cursor
+    let current_idx = if focused { tab.list_state.selected() } else { None };
 
     // Check if directory is empty
-    if app.files.is_empty() {
+    if tab.files.is_empty() {
         let empty_text = Line::from(Span::styled(
             "empty",
             Style::default()
@@ -43,16 +252,31 @@ fn render_file_list(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    let items: Vec<ListItem> = app.files
+    if tab.filtered_indices.is_empty() {
+        let empty_text = Line::from(Span::styled(
+            "no matches",
+            Style::default()
+                .fg(ratatui::style::Color::DarkGray)
+                .add_modifier(Modifier::ITALIC)
+        ));
+        frame.render_widget(Paragraph::new(empty_text), area);
+        return;
+    }
+
+    // Only entries passing the filter are listed, in filter order
+    let items: Vec<ListItem> = tab.filtered_indices
         .iter()
         .enumerate()
-        .map(|(idx, file)| {
+        .map(|(idx, &file_idx)| {
+            let file = &tab.files[file_idx];
             let is_cursor = current_idx == Some(idx);
             let is_selected = app.selected_paths.contains(&file.path);
-            let is_highlighted = app.search_highlights.contains(&idx);
             let is_flashing = app.flash_copied_paths.contains(&file.path);
-            let match_positions = app.search_match_positions.get(&idx);
-            create_list_item(file, is_cursor, is_selected, is_highlighted, is_flashing, match_positions, app)
+            let match_positions = tab.search_match_positions
+                .get(&file_idx)
+                .or_else(|| tab.filter_match_positions.get(&file_idx));
+            let dir_size = tab.dir_sizes.get(&file.path).copied().flatten();
+            create_list_item(file, is_cursor, is_selected, is_flashing, match_positions, dir_size, app)
         })
         .collect();
 
@@ -61,27 +285,467 @@ fn render_file_list(frame: &mut Frame, app: &App, area: Rect) {
         .highlight_style(Style::default()) // Empty style - we handle selection in create_list_item
         .highlight_symbol("");
 
-    frame.render_stateful_widget(list, area, &mut app.list_state.clone());
+    frame.render_stateful_widget(list, area, &mut tab.list_state.clone());
+}
+
+fn render_trash(frame: &mut Frame, app: &App, area: Rect) {
+    if app.trash_items.is_empty() {
+        let empty_text = Line::from(Span::styled(
+            "trash is empty",
+            Style::default()
+                .fg(ratatui::style::Color::DarkGray)
+                .add_modifier(Modifier::ITALIC)
+        ));
+        frame.render_widget(Paragraph::new(empty_text), area);
+        return;
+    }
+
+    let current_idx = app.trash_state.selected();
+    let items: Vec<ListItem> = app.trash_items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let color = if item.is_dir {
+                app.config.colors.directory
+            } else {
+                app.config.colors.file
+            };
+            let mut style = Style::default().fg(color);
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+            }
+
+            let date = item.deletion_date.replace('T', " ");
+            let mut original = item.original_path.to_string_lossy().to_string();
+            if item.is_dir {
+                original.push('/');
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", date), style),
+                Span::styled(original, style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, area, &mut app.trash_state.clone());
+}
+
+fn render_bookmarks(frame: &mut Frame, app: &App, area: Rect) {
+    const EMPTY: &str = "no bookmarks (press b and a key to bookmark a directory)";
+
+    // Wide enough for the longest bookmark, plus the borders
+    let longest = app.bookmarks
+        .entries()
+        .into_iter()
+        .map(|(key, dir)| key.chars().count() + 3 + dir.to_string_lossy().chars().count())
+        .max()
+        .unwrap_or(EMPTY.len());
+    let width = (longest.max(30) + 2).min(u16::MAX as usize) as u16;
+    let height = (app.bookmarks.len().max(1) + 2).min(u16::MAX as usize) as u16;
+    let area = centered_rect(width, height, area);
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Bookmarks ")
+        .border_style(Style::default().fg(ratatui::style::Color::DarkGray));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if app.bookmarks.is_empty() {
+        let empty_text = Line::from(Span::styled(
+            EMPTY,
+            Style::default()
+                .fg(ratatui::style::Color::DarkGray)
+                .add_modifier(Modifier::ITALIC)
+        ));
+        frame.render_widget(Paragraph::new(empty_text), inner);
+        return;
+    }
+
+    let current_idx = app.bookmarks_state.selected();
+    let items: Vec<ListItem> = app.bookmarks
+        .entries()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (key, dir))| {
+            // Bookmarks whose directory is gone are dimmed
+            let mut style = if dir.is_dir() {
+                Style::default().fg(app.config.colors.directory)
+            } else {
+                Style::default().fg(app.config.colors.hidden).add_modifier(Modifier::DIM)
+            };
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", key), style.add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{}/", dir.to_string_lossy()), style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, inner, &mut app.bookmarks_state.clone());
+}
+
+fn render_jobs(frame: &mut Frame, app: &App, area: Rect) {
+    if app.jobs.is_empty() {
+        let empty_text = Line::from(Span::styled(
+            "no running jobs",
+            Style::default()
+                .fg(ratatui::style::Color::DarkGray)
+                .add_modifier(Modifier::ITALIC)
+        ));
+        frame.render_widget(Paragraph::new(empty_text), area);
+        return;
+    }
+
+    // Each job takes two rows: a description and a progress gauge
+    let current_idx = app.jobs_state.selected();
+    for (idx, job) in app.jobs.jobs.iter().enumerate() {
+        let y = area.y + (idx as u16) * 2;
+        if y + 1 >= area.y + area.height {
+            break;
+        }
+
+        let mut style = Style::default();
+        if current_idx == Some(idx) {
+            style = style
+                .bg(app.config.colors.selected)
+                .fg(ratatui::style::Color::Black)
+                .add_modifier(Modifier::BOLD);
+        }
+
+        let status = if job.is_cancelled() { " (cancelling)" } else { "" };
+        let title = format!(
+            "{}{}  {} / {}  {}/{} files",
+            job.description,
+            status,
+            format_size(job.progress.bytes_done),
+            format_size(job.progress.bytes_total),
+            job.progress.files_done,
+            job.progress.files_total
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(title, style))),
+            Rect::new(area.x, y, area.width, 1),
+        );
+
+        let gauge = LineGauge::default()
+            .filled_style(Style::default().fg(app.config.colors.selected))
+            .ratio(job.progress.ratio());
+        frame.render_widget(gauge, Rect::new(area.x, y + 1, area.width, 1));
+    }
+}
+
+fn render_finder(frame: &mut Frame, app: &App, finder: &Finder, area: Rect) {
+    if finder.matches.is_empty() {
+        let message = if finder.scanning { "searching..." } else { "no matches" };
+        let empty_text = Line::from(Span::styled(
+            message,
+            Style::default()
+                .fg(ratatui::style::Color::DarkGray)
+                .add_modifier(Modifier::ITALIC)
+        ));
+        frame.render_widget(Paragraph::new(empty_text), area);
+        return;
+    }
+
+    let current_idx = finder.list_state.selected();
+    let items: Vec<ListItem> = finder.matches
+        .iter()
+        .enumerate()
+        .map(|(idx, found)| {
+            let candidate = &found.path;
+            let color = if candidate.ends_with('/') {
+                app.config.colors.directory
+            } else {
+                app.config.colors.file
+            };
+            let mut style = Style::default().fg(color);
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+            }
+
+            // Highlight matched characters in yellow like the regular search
+            let spans: Vec<Span> = candidate
+                .chars()
+                .enumerate()
+                .map(|(char_idx, ch)| {
+                    if found.positions.contains(&char_idx) {
+                        Span::styled(
+                            ch.to_string(),
+                            style.fg(ratatui::style::Color::Yellow).add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        Span::styled(ch.to_string(), style)
+                    }
+                })
+                .collect();
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, area, &mut finder.list_state.clone());
+}
+
+fn render_rename_plan(frame: &mut Frame, app: &App, plan: &RenamePlan, area: Rect) {
+    let name_of = |path: &std::path::Path| {
+        path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    };
+    let width = plan.renames.iter().map(|(from, _)| name_of(from).chars().count()).max().unwrap_or(0);
+
+    let current_idx = app.rename_plan_state.selected();
+    let items: Vec<ListItem> = plan.renames
+        .iter()
+        .enumerate()
+        .map(|(idx, (from, to))| {
+            let mut style = Style::default().fg(app.config.colors.file);
+            let mut new_style = Style::default().fg(ratatui::style::Color::Yellow);
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+                new_style = style;
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<width$}", name_of(from), width = width), style),
+                Span::styled("  ->  ", style),
+                Span::styled(name_of(to), new_style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, area, &mut app.rename_plan_state.clone());
+}
+
+fn render_pattern_rename(frame: &mut Frame, app: &App, pattern: &PatternRename, area: Rect) {
+    let names: Vec<String> = pattern.originals
+        .iter()
+        .map(|path| path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default())
+        .collect();
+    let width = names.iter().map(|name| name.chars().count()).max().unwrap_or(0);
+
+    let current_idx = pattern.list_state.selected();
+    let items: Vec<ListItem> = names
+        .iter()
+        .zip(&pattern.new_names)
+        .enumerate()
+        .map(|(idx, (old, new))| {
+            let mut style = Style::default().fg(app.config.colors.file);
+            // Unchanged names are dimmed, conflicts shown in red
+            let mut new_style = if pattern.conflicts.get(idx).copied().unwrap_or(false) {
+                Style::default().fg(ratatui::style::Color::Red).add_modifier(Modifier::BOLD)
+            } else if new == old {
+                style.add_modifier(Modifier::DIM)
+            } else {
+                Style::default().fg(ratatui::style::Color::Yellow)
+            };
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+                new_style = new_style.bg(app.config.colors.selected);
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<width$}", old, width = width), style),
+                Span::styled("  ->  ", style),
+                Span::styled(new.clone(), new_style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, area, &mut pattern.list_state.clone());
+}
+
+fn render_open_with(frame: &mut Frame, app: &App, area: Rect) {
+    let current_idx = app.open_with_state.selected();
+    let items: Vec<ListItem> = app.open_with_choices
+        .iter()
+        .enumerate()
+        .map(|(idx, choice)| {
+            let mut style = Style::default().fg(app.config.colors.file);
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+            }
+            let (label, detail) = match choice {
+                Some(opener) => (
+                    opener.label().to_string(),
+                    if opener.terminal { "terminal" } else { "detached" },
+                ),
+                None => (String::from("System default"), "default application"),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", idx + 1), style.add_modifier(Modifier::BOLD)),
+                Span::styled(label, style),
+                Span::styled(format!("  ({})", detail), style.add_modifier(Modifier::DIM)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, area, &mut app.open_with_state.clone());
+}
+
+fn render_jump(frame: &mut Frame, app: &App, area: Rect) {
+    if app.jump_matches.is_empty() {
+        let empty_text = Line::from(Span::styled(
+            "no matching directories visited yet",
+            Style::default()
+                .fg(ratatui::style::Color::DarkGray)
+                .add_modifier(Modifier::ITALIC)
+        ));
+        frame.render_widget(Paragraph::new(empty_text), area);
+        return;
+    }
+
+    let current_idx = app.jump_state.selected();
+    let items: Vec<ListItem> = app.jump_matches
+        .iter()
+        .enumerate()
+        .map(|(idx, found)| {
+            let mut style = Style::default().fg(app.config.colors.directory);
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+            }
+
+            let mut spans = vec![Span::styled(format!("{:>7.1}  ", found.score), style)];
+            spans.extend(found.path.to_string_lossy().chars().enumerate().map(|(char_idx, ch)| {
+                if found.positions.contains(&char_idx) {
+                    Span::styled(
+                        ch.to_string(),
+                        style.fg(ratatui::style::Color::Yellow).add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    Span::styled(ch.to_string(), style)
+                }
+            }));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, area, &mut app.jump_state.clone());
+}
+
+fn render_grep(frame: &mut Frame, app: &App, grep: &Grep, area: Rect) {
+    if grep.matches.is_empty() {
+        let message = if grep.scanning { "searching..." } else { "no matches" };
+        let empty_text = Line::from(Span::styled(
+            message,
+            Style::default()
+                .fg(ratatui::style::Color::DarkGray)
+                .add_modifier(Modifier::ITALIC)
+        ));
+        frame.render_widget(Paragraph::new(empty_text), area);
+        return;
+    }
+
+    let current_idx = grep.list_state.selected();
+    let items: Vec<ListItem> = grep.matches
+        .iter()
+        .enumerate()
+        .map(|(idx, found)| {
+            let mut style = Style::default();
+            let mut path_style = Style::default().fg(app.config.colors.directory);
+            let mut line_style = Style::default().fg(ratatui::style::Color::DarkGray);
+            if current_idx == Some(idx) {
+                style = style
+                    .bg(app.config.colors.selected)
+                    .fg(ratatui::style::Color::Black)
+                    .add_modifier(Modifier::BOLD);
+                path_style = style;
+                line_style = style;
+            }
+            let match_style = style.fg(ratatui::style::Color::Yellow).add_modifier(Modifier::BOLD);
+
+            let mut spans = vec![
+                Span::styled(found.path.to_string_lossy().to_string(), path_style),
+                Span::styled(format!(":{}: ", found.line_number), line_style),
+            ];
+
+            // Highlight the matched text like the fuzzy search does
+            let snippet = &found.snippet;
+            match found.column {
+                Some(start) => {
+                    let end = start + grep.query.len();
+                    spans.push(Span::styled(snippet[..start].replace('\t', "    "), style));
+                    spans.push(Span::styled(snippet[start..end].replace('\t', "    "), match_style));
+                    spans.push(Span::styled(snippet[end..].replace('\t', "    "), style));
+                }
+                None => spans.push(Span::styled(snippet.replace('\t', "    "), style)),
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::NONE))
+        .highlight_style(Style::default())
+        .highlight_symbol("");
+
+    frame.render_stateful_widget(list, area, &mut grep.list_state.clone());
 }
 
 fn create_list_item<'a>(
     file: &'a FileEntry,
     is_cursor: bool,
     is_selected: bool,
-    _is_highlighted: bool,
     is_flashing: bool,
     match_positions: Option<&Vec<usize>>,
+    dir_size: Option<u64>,
     app: &App
 ) -> ListItem<'a> {
-    let icon = if file.is_dir {
-        ""
-    } else if file.is_symlink {
-        ""
-    } else if file.is_executable {
-        ""
-    } else {
-        ""
-    };
+    let icon = "";
 
     // Check if file is in cut clipboard
     let is_cut = match &app.clipboard {
@@ -89,24 +753,7 @@ fn create_list_item<'a>(
         _ => false,
     };
 
-    let color = if file.is_hidden {
-        app.config.colors.hidden
-    } else if file.is_dir {
-        app.config.colors.directory
-    } else if file.is_symlink {
-        app.config.colors.symlink
-    } else if file.is_executable {
-        app.config.colors.executable
-    } else {
-        app.config.colors.file
-    };
-
-    let mut base_style = Style::default().fg(color);
-
-    // Make directories bold
-    if file.is_dir {
-        base_style = base_style.add_modifier(Modifier::BOLD);
-    }
+    let mut base_style = entry_style(file, app);
 
     // Flash effect for copied files (yellow background) - takes precedence
     if is_flashing {
@@ -137,9 +784,9 @@ fn create_list_item<'a>(
 
     let size_str = if file.is_dir {
         // Check if we have the calculated size for this directory
-        match app.dir_sizes.get(&file.path) {
-            Some(Some(size)) => format_size(*size),
-            Some(None) | None => String::from("? B"), // Still calculating
+        match dir_size {
+            Some(size) => format_size(size),
+            None => String::from("? B"), // Still calculating
         }
     } else {
         format_size(file.size)
@@ -188,11 +835,31 @@ fn create_list_item<'a>(
 }
 
 fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
+    // Reserve the right side of the footer for a progress gauge while jobs are running
+    let area = if app.jobs.is_empty() {
+        area
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(1), Constraint::Length(32)])
+            .split(area);
+        let progress = app.jobs.total_progress();
+        let label = format!("{} job(s) {:>3.0}%", app.jobs.jobs.len(), progress.ratio() * 100.0);
+        let gauge = LineGauge::default()
+            .label(label)
+            .filled_style(Style::default().fg(app.config.colors.selected))
+            .ratio(progress.ratio());
+        frame.render_widget(gauge, chunks[1]);
+        chunks[0]
+    };
+
     // Show error message if present, otherwise show current path
     let display_path = if let Some(ref error) = app.error_message {
         format!("Error: {}", error)
+    } else if let Some(ref status) = app.status_message {
+        status.clone()
     } else {
-        app.current_dir.to_string_lossy().to_string()
+        display_dir(&app.tab)
     };
 
     let footer_text = match app.mode {
@@ -205,11 +872,24 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
             let sort_order = if app.sort_ascending { "↑" } else { "↓" };
             let sort_info = format!("{} {}", sort_name, sort_order);
 
-            let right_info = match &app.clipboard {
+            let mut right_info = match &app.clipboard {
                 ClipboardOperation::Copy(paths) => format!("Sort: {} | Copied: {}", sort_info, paths.len()),
                 ClipboardOperation::Cut(paths) => format!("Sort: {} | Cut: {}", sort_info, paths.len()),
                 ClipboardOperation::None => format!("Sort: {}", sort_info),
             };
+            if !app.tab.filter_query.is_empty() {
+                right_info = format!("Filter: {} | {}", app.tab.filter_query, right_info);
+            }
+            if let Some(pending) = pending_input(app) {
+                right_info = format!("{} | {}", pending, right_info);
+            }
+            if !app.tab.search_highlights.is_empty() {
+                let matches = match app.current_match() {
+                    Some((current, total)) => format!("match {}/{}", current, total),
+                    None => format!("{} matches", app.tab.search_highlights.len()),
+                };
+                right_info = format!("{} | {}", matches, right_info);
+            }
 
             // Calculate padding needed between left and right sections
             let total_len = display_path.len() + right_info.len();
@@ -225,7 +905,10 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
             }
         }
         Mode::VisualMulti => {
-            let right_info = "Multi-Select: j/k=add m=remove ENTER=keep ESC=clear";
+            let mut right_info = String::from("Multi-Select: j/k=add m=remove ENTER=keep ESC=clear");
+            if let Some(pending) = pending_input(app) {
+                right_info = format!("{} | {}", pending, right_info);
+            }
             let total_len = display_path.len() + right_info.len();
             let available_width = area.width as usize;
 
@@ -236,7 +919,20 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
                 display_path
             }
         }
-        Mode::Search => format!("Search: {}", app.search_query),
+        Mode::Search => format!("Search: {}", app.tab.search_query),
+        Mode::Filter => {
+            let left_info = format!("Filter: {}", app.tab.filter_query);
+            let right_info = format!("{}/{}", app.tab.filtered_indices.len(), app.tab.files.len());
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
         Mode::SortMenu => {
             let right_info = "Sort: [n]ame [s]ize [m]odified ESC=cancel";
             let total_len = display_path.len() + right_info.len();
@@ -250,8 +946,192 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
             }
         }
         Mode::Create => format!("Create (end with / for folder): {}", app.create_input),
+        Mode::Compress => format!("Archive name (.zip .tar.gz .tar.zst): {}", app.compress_input),
         Mode::Rename => format!("Rename: {}", app.rename_input),
         Mode::Help => String::from("Press ESC or ? to close help"),
+        Mode::PasteConflict => {
+            let name = app.pending_paste
+                .as_ref()
+                .and_then(|p| p.remaining.first())
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            format!(
+                "{} exists: [o]verwrite [s]kip [r]ename [n]ewer wins (Shift=all) ESC=cancel",
+                name
+            )
+        }
+        Mode::GrepInput => format!("Grep: {}", app.grep_input),
+        Mode::BookmarkSet => String::from("Bookmark this directory as (press a key, ESC=cancel):"),
+        Mode::BookmarkJump => {
+            let keys: String = app.bookmarks.entries().iter().map(|(key, _)| key.as_str()).collect();
+            format!("Jump to bookmark [{}] (ESC=cancel):", keys)
+        }
+        Mode::Bookmarks => {
+            let left_info = if let Some(ref error) = app.error_message {
+                format!("Error: {}", error)
+            } else {
+                format!("Bookmarks: {}", app.bookmarks.len())
+            };
+            let right_info = "ENTER=jump d=delete ESC=close";
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::Grep => {
+            let (query, matches, files, scanning) = app.grep
+                .as_ref()
+                .map(|g| (g.query.as_str(), g.matches.len(), g.files_searched, g.scanning))
+                .unwrap_or(("", 0, 0, false));
+            let left_info = if let Some(ref error) = app.error_message {
+                format!("Error: {}", error)
+            } else {
+                format!("Grep: {}", query)
+            };
+            let right_info = format!(
+                "{} matches in {} files{} | ENTER=jump S=new search ESC=close",
+                matches,
+                files,
+                if scanning { " (searching...)" } else { "" }
+            );
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::RenameConfirm => {
+            let count = app.rename_plan.as_ref().map(|plan| plan.renames.len()).unwrap_or(0);
+            let left_info = format!("Rename {} item(s)? (y/n)", count);
+            let right_info = "j/k=scroll y=apply n=cancel";
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::PatternRename => {
+            let (find, replace, changed, conflicts, error) = app.pattern_rename
+                .as_ref()
+                .map(|p| (p.find.as_str(), p.replace.as_str(), p.changed_count(), p.conflict_count(), p.error.clone()))
+                .unwrap_or(("", "", 0, 0, None));
+            let left_info = format!("Regex: {}  Replace: {}", find, replace);
+            let status = match (app.error_message.clone().or(error), conflicts) {
+                (Some(error), _) => format!("Error: {}", error),
+                (None, 0) => format!("{} to rename", changed),
+                (None, n) => format!("{} conflict(s)", n),
+            };
+            let right_info = format!("{} | TAB=switch ENTER=apply ESC=cancel", status);
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::OpenWith => {
+            let name = app.open_with_path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let left_info = format!("Open {} with:", name);
+            let right_info = "ENTER/1-9=open ESC=cancel";
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::Jump => {
+            let left_info = format!("Jump: {}", app.jump_input);
+            let right_info = format!("{} directories", app.jump_matches.len());
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::Finder => {
+            let (query, shown, total, scanning) = app.finder
+                .as_ref()
+                .map(|f| (f.query.as_str(), f.matches.len(), f.total, f.scanning))
+                .unwrap_or(("", 0, 0, false));
+            let left_info = format!("Find: {}", query);
+            let right_info = format!("{}/{}{}", shown, total, if scanning { " scanning..." } else { "" });
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::Jobs => {
+            let right_info = "x=cancel job ESC=close";
+            let total_len = display_path.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", display_path, " ".repeat(padding), right_info)
+            } else {
+                display_path
+            }
+        }
+        Mode::Trash => {
+            let left_info = if let Some(ref error) = app.error_message {
+                format!("Error: {}", error)
+            } else {
+                format!("Trash: {} items", app.trash_items.len())
+            };
+            let right_info = "r/ENTER=restore D=delete forever ESC=close";
+            let total_len = left_info.len() + right_info.len();
+            let available_width = area.width as usize;
+
+            if total_len < available_width {
+                let padding = available_width.saturating_sub(total_len);
+                format!("{}{}{}", left_info, " ".repeat(padding), right_info)
+            } else {
+                left_info
+            }
+        }
+        Mode::PurgeConfirm => {
+            let name = app
+                .trash_state
+                .selected()
+                .and_then(|i| app.trash_items.get(i))
+                .map(|item| item.original_path.file_name().unwrap_or_default().to_string_lossy().to_string())
+                .unwrap_or_default();
+            format!("Delete {} forever? [y/N]", name)
+        }
         Mode::DeleteConfirm => {
             let count = app.delete_targets.len();
             if count == 1 {
@@ -280,16 +1160,71 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
             let cursor_y = area.y;
             frame.set_cursor_position((cursor_x, cursor_y));
         }
+        Mode::GrepInput => {
+            // "Grep: " is 6 characters
+            let cursor_x = area.x + 6 + app.grep_input.chars().count() as u16;
+            frame.set_cursor_position((cursor_x, area.y));
+        }
+        Mode::Filter => {
+            // "Filter: " is 8 characters
+            let cursor_x = area.x + 8 + app.tab.filter_query.chars().count() as u16;
+            frame.set_cursor_position((cursor_x, area.y));
+        }
+        Mode::Jump => {
+            // "Jump: " is 6 characters
+            let cursor_x = area.x + 6 + app.jump_input.chars().count() as u16;
+            frame.set_cursor_position((cursor_x, area.y));
+        }
+        Mode::Finder => {
+            // "Find: " is 6 characters
+            let query_len = app.finder.as_ref().map(|f| f.query.chars().count()).unwrap_or(0);
+            frame.set_cursor_position((area.x + 6 + query_len as u16, area.y));
+        }
+        Mode::PatternRename => {
+            // "Regex: " is 7 characters, "  Replace: " 11
+            if let Some(pattern) = &app.pattern_rename {
+                let mut cursor_x = area.x + 7 + pattern.find.chars().count() as u16;
+                if pattern.editing_replace {
+                    cursor_x += 11 + pattern.replace.chars().count() as u16;
+                }
+                frame.set_cursor_position((cursor_x, area.y));
+            }
+        }
         Mode::Create => {
             // "Create (end with / for folder): " is 32 characters
             let cursor_x = area.x + 32 + app.create_input.len() as u16;
             let cursor_y = area.y;
             frame.set_cursor_position((cursor_x, cursor_y));
         }
+        Mode::Compress => {
+            // "Archive name (.zip .tar.gz .tar.zst): " is 38 characters
+            let cursor_x = area.x + 38 + app.compress_input.chars().count() as u16;
+            frame.set_cursor_position((cursor_x, area.y));
+        }
         _ => {}
     }
 }
 
+// The tab's directory; inside an archive as `/path/to/archive.zip::/dir`
+fn display_dir(tab: &Tab) -> String {
+    match &tab.archive {
+        Some(archive) => {
+            let inner = tab.current_dir.strip_prefix(&archive.path).unwrap_or(&tab.current_dir);
+            format!("{}::/{}", archive.path.display(), inner.display())
+        }
+        None => tab.current_dir.to_string_lossy().to_string(),
+    }
+}
+
+// Count and keys typed so far for a command, e.g. "5" or "g"
+fn pending_input(app: &App) -> Option<String> {
+    if app.pending_count.is_none() && app.pending_keys.is_empty() {
+        return None;
+    }
+    let count = app.pending_count.map(|c| c.to_string()).unwrap_or_default();
+    Some(format!("{}{}", count, format_sequence(&app.pending_keys)))
+}
+
 fn render_help(frame: &mut Frame, app: &App, area: Rect) {
     let help_text = match app.mode {
         Mode::Help => vec![
@@ -300,19 +1235,35 @@ fn render_help(frame: &mut Frame, app: &App, area: Rect) {
             Line::from("  j/↓     - Move down"),
             Line::from("  k/↑     - Move up"),
             Line::from("  h/←     - Go to parent directory"),
-            Line::from("  l/→     - Enter directory / Open file"),
+            Line::from("  l/→     - Enter directory or archive / Open file"),
+            Line::from("  5j/10k  - Counts repeat motions; 12G jumps to line 12"),
+            Line::from("  3m/3yy  - Counts also apply to mark, yank, cut and delete"),
             Line::from(""),
             Line::from("File Operations:"),
             Line::from("  a       - Create file/folder"),
+            Line::from("  e       - Edit file(s) in $VISUAL / $EDITOR"),
+            Line::from("  O       - Open with... (configured openers)"),
+            Line::from("  E       - Bulk rename marked/visible entries in $EDITOR"),
+            Line::from("  Ctrl+E  - Rename marked/visible entries by regex or template"),
             Line::from("  yy      - Copy current file"),
             Line::from("  x       - Cut current file"),
-            Line::from("  p       - Paste"),
-            Line::from("  d       - Delete file/folder"),
+            Line::from("  p       - Paste (runs in the background)"),
+            Line::from("  C       - Compress current/marked files into a zip or tarball"),
+            Line::from("  X       - Extract archive into a new directory next to it"),
+            Line::from("  J       - Show jobs panel (x=cancel job)"),
+            Line::from("  d       - Delete file/folder (to trash by default)"),
+            Line::from("  T       - Browse trash (r=restore, D=delete forever)"),
+            Line::from("  b<key>  - Bookmark current directory"),
+            Line::from("  '<key>  - Jump to bookmark"),
+            Line::from("  B       - Browse bookmarks (d=delete)"),
+            Line::from("  u       - Undo last file operation"),
+            Line::from("  Ctrl+R  - Redo undone file operation"),
             Line::from(""),
             Line::from("Marking:"),
             Line::from("  m       - Toggle mark on current file"),
             Line::from("  y       - Copy all marked files"),
             Line::from("  x       - Cut all marked files"),
+            Line::from("  C       - Compress all marked files"),
             Line::from("  d       - Delete all marked files"),
             Line::from(""),
             Line::from("Multi-Select (Shift+V):"),
@@ -321,16 +1272,34 @@ fn render_help(frame: &mut Frame, app: &App, area: Rect) {
             Line::from("  m       - Remove current file from selection"),
             Line::from("  y       - Copy selection and exit"),
             Line::from("  x       - Cut selection and exit"),
+            Line::from("  C       - Compress selection"),
             Line::from("  ENTER   - Exit and keep marks"),
             Line::from("  ESC     - Exit and clear all marks"),
             Line::from(""),
             Line::from("Other:"),
             Line::from("  /       - Search (fuzzy)"),
+            Line::from("  n/N     - Next / previous search match"),
+            Line::from("  *       - Search for the name under the cursor"),
+            Line::from("  F       - Filter listing (ESC clears)"),
+            Line::from("  f       - Find files recursively (fuzzy)"),
+            Line::from("  S       - Search file contents (grep)"),
+            Line::from("  z       - Jump to a frequently visited directory"),
             Line::from("  .       - Toggle hidden files"),
+            Line::from("  t       - Open new tab"),
+            Line::from("  gt/gT   - Next / previous tab"),
+            Line::from("  Ctrl+W  - Close tab"),
+            Line::from("  |       - Toggle dual-pane mode"),
+            Line::from("  Tab     - Switch pane (dual-pane mode)"),
+            Line::from("  F5/F6   - Copy / move to the other pane"),
+            Line::from("  P       - Toggle preview pane"),
+            Line::from("  M       - Toggle parent/current/child columns"),
             Line::from("  s       - Sort menu"),
             Line::from("  o       - Toggle sort order (↑/↓)"),
             Line::from("  ?       - Show this help"),
             Line::from("  q       - Quit"),
+            Line::from("  Q       - Quit and cd the shell here (needs jumper init)"),
+            Line::from(""),
+            Line::from("These are the default keys; remap them under [keybindings.normal] and [keybindings.visual]"),
             Line::from(""),
             Line::from("Press ESC to return").bold(),
         ],
@@ -0,0 +1,38 @@
+use ignore::{WalkBuilder, WalkState};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Walks the tree below `root` on several threads, calling `visit` with each path and whether it
+/// is a directory. Hidden entries are skipped unless `show_hidden` is set, ignore rules are honoured
+/// (`.gitignore` and `.ignore` files, `.git/info/exclude` and the global git excludes) and the walk
+/// stops early when `cancel` is set or `visit` returns false.
+pub fn walk(root: &Path, show_hidden: bool, cancel: &AtomicBool, visit: impl Fn(&Path, bool) -> bool + Sync) {
+    let walker = WalkBuilder::new(root)
+        .hidden(!show_hidden)
+        .require_git(false) // a .gitignore also applies outside a repository
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build_parallel();
+
+    walker.run(|| {
+        let visit = &visit;
+        Box::new(move |entry| {
+            if cancel.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            // The root itself is not reported
+            if entry.depth() == 0 {
+                return WalkState::Continue;
+            }
+
+            let is_dir = entry.file_type().is_some_and(|file_type| file_type.is_dir());
+            if visit(entry.path(), is_dir) {
+                WalkState::Continue
+            } else {
+                WalkState::Quit
+            }
+        })
+    });
+}
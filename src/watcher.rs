@@ -0,0 +1,84 @@
+use anyhow::Result;
+use notify::event::{AccessKind, Event, EventKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+// Bursts of events (a build, an extracting archive) arrive as one reload
+const DEBOUNCE: Duration = Duration::from_millis(200);
+// A steady stream of events still reloads this often
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// Watches the listed directories (not their subtrees) for changes
+pub struct DirWatcher {
+    watcher: RecommendedWatcher,
+    watched: Vec<PathBuf>,
+    rx: mpsc::UnboundedReceiver<Vec<PathBuf>>,
+    pending: HashSet<PathBuf>,
+    first_event: Option<Instant>,
+    last_event: Option<Instant>,
+}
+
+impl DirWatcher {
+    pub fn new() -> Result<Self> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            // Errors (e.g. a watched directory vanishing) show up as missing directories on reload.
+            // Opening and reading change nothing, and previews do that all the time.
+            if let Ok(event) = result {
+                if !matches!(event.kind, EventKind::Access(AccessKind::Open(_) | AccessKind::Read)) {
+                    let _ = tx.send(event.paths);
+                }
+            }
+        })?;
+
+        Ok(Self {
+            watcher,
+            watched: Vec::new(),
+            rx,
+            pending: HashSet::new(),
+            first_event: None,
+            last_event: None,
+        })
+    }
+
+    /// Watches exactly `dirs`, adding and removing watches as needed
+    pub fn watch(&mut self, dirs: &[&Path]) {
+        let watcher = &mut self.watcher;
+        self.watched.retain(|dir| {
+            let keep = dirs.contains(&dir.as_path());
+            if !keep {
+                let _ = watcher.unwatch(dir);
+            }
+            keep
+        });
+        for dir in dirs {
+            if !self.watched.iter().any(|watched| watched == dir)
+                && watcher.watch(dir, RecursiveMode::NonRecursive).is_ok()
+            {
+                self.watched.push(dir.to_path_buf());
+            }
+        }
+    }
+
+    /// Paths that changed since the last call, once events have settled
+    pub fn changed_paths(&mut self) -> HashSet<PathBuf> {
+        while let Ok(paths) = self.rx.try_recv() {
+            let now = Instant::now();
+            self.pending.extend(paths);
+            self.first_event.get_or_insert(now);
+            self.last_event = Some(now);
+        }
+
+        let settled = self.last_event.is_some_and(|at| at.elapsed() >= DEBOUNCE)
+            || self.first_event.is_some_and(|at| at.elapsed() >= MAX_DELAY);
+        if !settled {
+            return HashSet::new();
+        }
+        self.first_event = None;
+        self.last_event = None;
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -5,20 +5,20 @@ echo ""
 echo "Current directory: $(pwd)"
 echo ""
 
-# Remove old lastdir file
-rm -f ~/.cache/jumper/lastdir
+CWD_FILE="$(mktemp -t jumper-cwd.XXXXXX)"
 
-echo "Running jumper binary directly (will fail in non-interactive mode)..."
-jumper 2>&1 || true
+echo "Running jumper binary directly with --cwd-file (quit with Q to write it)..."
+command jumper --cwd-file "$CWD_FILE" 2>&1 || true
 
 echo ""
-echo "Checking if lastdir was created..."
-if [ -f ~/.cache/jumper/lastdir ]; then
-    echo "✓ lastdir file exists"
-    echo "Contents: $(cat ~/.cache/jumper/lastdir)"
+echo "Checking if the cwd file was written..."
+if [ -s "$CWD_FILE" ]; then
+    echo "✓ cwd file written"
+    echo "Contents: $(cat "$CWD_FILE")"
 else
-    echo "✗ lastdir file NOT created"
+    echo "✗ cwd file empty (quit with q, or jumper failed)"
 fi
+rm -f "$CWD_FILE"
 
 echo ""
 echo "=== Shell wrapper test ==="